@@ -0,0 +1,149 @@
+//! Flood filling over tiles, for finding out which tiles are connected to each other.
+
+use {
+    std::collections::{VecDeque},
+
+    cgmath::{Point2, Vector2},
+
+    lagato::{grid::{Tiles}},
+
+    state::ship::{Tile},
+    Error,
+};
+
+/// Connected areas of tiles, every area labeled with its own number starting from 0.
+pub struct Labels {
+    size: Vector2<i32>,
+    labels: Vec<Option<u32>>,
+    amount: u32,
+}
+
+impl Labels {
+    pub fn new() -> Self {
+        Labels {
+            size: Vector2::new(0, 0),
+            labels: Vec::new(),
+            amount: 0,
+        }
+    }
+
+    /// Labels all tiles that are passable with the area they're in. Only straight neighbors
+    /// connect tiles, diagonals would leak through corners.
+    pub fn build<F>(tiles: &Tiles<Tile>, mut is_passable: F) -> Result<Self, Error>
+        where F: FnMut(&Tile) -> Result<bool, Error>
+    {
+        let size = tiles.size();
+        let mut labels = Labels {
+            size,
+            labels: vec!(None; (size.x * size.y) as usize),
+            amount: 0,
+        };
+
+        for position in tiles.iter_pos() {
+            if labels.label_at(position).is_some() || !is_passable(tiles.get(position)?)? {
+                continue
+            }
+
+            // This tile isn't in an area yet, so flood fill a new one from it
+            let label = labels.amount;
+            labels.amount += 1;
+
+            labels.set_label(position, label);
+            flood_fill(tiles, vec!(position), |neighbor, tile| {
+                if labels.label_at(neighbor).is_some() || !is_passable(tile)? {
+                    return Ok(false)
+                }
+
+                labels.set_label(neighbor, label);
+                Ok(true)
+            })?;
+        }
+
+        Ok(labels)
+    }
+
+    pub fn amount(&self) -> u32 {
+        self.amount
+    }
+
+    pub fn label_at(&self, position: Point2<i32>) -> Option<u32> {
+        if position.x < 0 || position.y < 0 ||
+           position.x >= self.size.x || position.y >= self.size.y {
+            return None
+        }
+
+        self.labels[self.index(position)]
+    }
+
+    fn set_label(&mut self, position: Point2<i32>, label: u32) {
+        let index = self.index(position);
+        self.labels[index] = Some(label);
+    }
+
+    fn index(&self, position: Point2<i32>) -> usize {
+        (position.x + position.y * self.size.x) as usize
+    }
+}
+
+/// Spreads out over straight neighbors from the start positions. Every neighbor that's on the
+/// tiles is passed to `claim`, which marks it and returns true if the fill should continue from
+/// there, or returns false if it was already reached or can't be passed through.
+pub fn flood_fill<F>(
+    tiles: &Tiles<Tile>, start: Vec<Point2<i32>>, mut claim: F,
+) -> Result<(), Error> where F: FnMut(Point2<i32>, &Tile) -> Result<bool, Error> {
+    let mut open = VecDeque::from(start);
+    while let Some(current) = open.pop_front() {
+        for neighbor in &straight_neighbors(current) {
+            if let Ok(tile) = tiles.get(*neighbor) {
+                if claim(*neighbor, tile)? {
+                    open.push_back(*neighbor);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn straight_neighbors(position: Point2<i32>) -> [Point2<i32>; 4] {
+    [
+        Point2::new(position.x + 1, position.y),
+        Point2::new(position.x - 1, position.y),
+        Point2::new(position.x, position.y + 1),
+        Point2::new(position.x, position.y - 1),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        flood::{Labels},
+        pathfinding::{Walkable},
+        test_utils::{self, DOOR, WALL},
+    };
+
+    #[test]
+    fn doors_split_rooms_but_not_regions() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 4));
+        test_utils::place(&mut ship, &object_classes, Point2::new(3, 1), WALL);
+        test_utils::place(&mut ship, &object_classes, Point2::new(3, 2), DOOR);
+
+        let rooms = Labels::build(&ship.tiles, |tile| {
+            Ok(tile.walkable(&object_classes)? == Walkable::Always)
+        }).unwrap();
+        let regions = Labels::build(&ship.tiles, |tile| {
+            Ok(tile.walkable(&object_classes)? != Walkable::Never)
+        }).unwrap();
+
+        assert_eq!(rooms.amount(), 2);
+        assert_eq!(rooms.label_at(Point2::new(3, 2)), None);
+        assert_ne!(rooms.label_at(Point2::new(1, 1)), rooms.label_at(Point2::new(5, 2)));
+
+        assert_eq!(regions.amount(), 1);
+        assert_eq!(regions.label_at(Point2::new(1, 1)), regions.label_at(Point2::new(5, 2)));
+        assert_eq!(regions.label_at(Point2::new(0, 0)), None);
+    }
+}
@@ -10,16 +10,20 @@ extern crate lagato;
 pub mod object_class;
 pub mod state;
 pub mod pathfinding;
+mod flood;
+#[cfg(test)]
+mod test_utils;
 
 use {
     object_class::{ObjectClassId},
-    state::ship::{TaskId},
+    state::ship::{TaskId, UnitId},
 };
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     InvalidClassId(ObjectClassId),
     InvalidTaskId(TaskId),
+    InvalidUnitId(UnitId),
     Tiles(lagato::grid::Error),
 }
 
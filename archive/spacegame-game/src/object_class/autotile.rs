@@ -0,0 +1,133 @@
+use {
+    cgmath::{Point2, Vector2},
+    ggez::graphics::{Rect},
+
+    lagato::{grid::{Tiles}},
+
+    object_class::{ObjectClassId},
+    state::ship::{Tile},
+};
+
+pub const NORTH: usize = 1;
+pub const EAST: usize = 2;
+pub const SOUTH: usize = 4;
+pub const WEST: usize = 8;
+pub const NORTH_EAST: usize = 16;
+pub const SOUTH_EAST: usize = 32;
+pub const SOUTH_WEST: usize = 64;
+pub const NORTH_WEST: usize = 128;
+
+/// Picks the sprite for an object depending on which neighbors have an object of the same class,
+/// so connected objects such as walls look like one piece.
+pub struct Autotile {
+    pub neighbors: AutotileNeighbors,
+    /// One sprite for every combination of neighbors, indexed by the neighbor mask. That's 16
+    /// sprites for straight neighbors, or 256 when including diagonals.
+    pub sprites: Vec<Rect>,
+}
+
+impl Autotile {
+    /// Gets the sprite for the object's neighbors, or None if there's no sprite for that
+    /// combination of neighbors.
+    pub fn sprite(
+        &self, tiles: &Tiles<Tile>, position: Point2<i32>, class: ObjectClassId,
+    ) -> Option<Rect> {
+        self.sprites.get(neighbor_mask(tiles, position, class, self.neighbors)).cloned()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AutotileNeighbors {
+    Straight,
+    /// Diagonals only count if both straight neighbors next to them do too, otherwise they
+    /// wouldn't be visibly connected.
+    All,
+}
+
+/// Finds which neighbors of a tile have an object of the given class, as a combination of the
+/// direction flags.
+pub fn neighbor_mask(
+    tiles: &Tiles<Tile>, position: Point2<i32>, class: ObjectClassId,
+    neighbors: AutotileNeighbors,
+) -> usize {
+    let has_class = |x, y| {
+        tiles.get(position + Vector2::new(x, y)).ok()
+            .and_then(|tile| tile.object.as_ref())
+            .map(|object| object.class == class)
+            .unwrap_or(false)
+    };
+
+    let mut mask = 0;
+    for &(x, y, flag) in &[(0, 1, NORTH), (1, 0, EAST), (0, -1, SOUTH), (-1, 0, WEST)] {
+        if has_class(x, y) {
+            mask |= flag;
+        }
+    }
+
+    if neighbors == AutotileNeighbors::All {
+        let diagonals = [
+            (1, 1, NORTH_EAST, NORTH | EAST),
+            (1, -1, SOUTH_EAST, SOUTH | EAST),
+            (-1, -1, SOUTH_WEST, SOUTH | WEST),
+            (-1, 1, NORTH_WEST, NORTH | WEST),
+        ];
+        for &(x, y, flag, required) in &diagonals {
+            if mask & required == required && has_class(x, y) {
+                mask |= flag;
+            }
+        }
+    }
+
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        object_class::{AutotileNeighbors, neighbor_mask, NORTH, EAST, WEST, NORTH_EAST},
+        state::ship::{Ship},
+        test_utils::{self, WALL},
+    };
+
+    fn walls(positions: &[(i32, i32)]) -> Ship {
+        let object_classes = test_utils::object_classes();
+        let mut ship = Ship::empty(Vector2::new(5, 5));
+        for &(x, y) in positions {
+            test_utils::place(&mut ship, &object_classes, Point2::new(x, y), WALL);
+        }
+        ship
+    }
+
+    #[test]
+    fn isolated_wall_has_no_neighbors() {
+        let ship = walls(&[(2, 2)]);
+        let mask = neighbor_mask(&ship.tiles, Point2::new(2, 2), WALL, AutotileNeighbors::All);
+        assert_eq!(mask, 0);
+    }
+
+    #[test]
+    fn straight_run_connects_both_sides() {
+        let ship = walls(&[(1, 2), (2, 2), (3, 2)]);
+        let mask = neighbor_mask(
+            &ship.tiles, Point2::new(2, 2), WALL, AutotileNeighbors::Straight,
+        );
+        assert_eq!(mask, EAST | WEST);
+    }
+
+    #[test]
+    fn diagonal_needs_both_sides() {
+        let ship = walls(&[(2, 2), (3, 3)]);
+        let mask = neighbor_mask(&ship.tiles, Point2::new(2, 2), WALL, AutotileNeighbors::All);
+        assert_eq!(mask, 0);
+
+        let ship = walls(&[(2, 2), (3, 3), (2, 3)]);
+        let mask = neighbor_mask(&ship.tiles, Point2::new(2, 2), WALL, AutotileNeighbors::All);
+        assert_eq!(mask, NORTH);
+
+        let ship = walls(&[(2, 2), (3, 3), (2, 3), (3, 2)]);
+        let mask = neighbor_mask(&ship.tiles, Point2::new(2, 2), WALL, AutotileNeighbors::All);
+        assert_eq!(mask, NORTH | EAST | NORTH_EAST);
+    }
+}
@@ -0,0 +1,8 @@
+/// Lets objects of a class fight fires around them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Extinguisher {
+    /// How many tiles away fires still get put out.
+    pub radius: f32,
+    /// How much fire intensity gets put out per second.
+    pub strength: f32,
+}
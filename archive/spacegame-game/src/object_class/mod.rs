@@ -1,14 +1,26 @@
+mod autotile;
 mod door;
+mod extinguisher;
+mod walkway;
 
 pub use self::{
+    autotile::{
+        Autotile, AutotileNeighbors, neighbor_mask,
+        NORTH, EAST, SOUTH, WEST, NORTH_EAST, SOUTH_EAST, SOUTH_WEST, NORTH_WEST,
+    },
     door::{DoorObjectBehavior},
+    extinguisher::{Extinguisher},
+    walkway::{WalkwayObjectBehavior},
 };
 
 use {
+    cgmath::{Point2},
     ggez::graphics::{Rect},
 
-    pathfinding::{Walkable},
-    state::ship::{Object},
+    lagato::{grid::{Tiles}},
+
+    pathfinding::{Walkable, WalkCost},
+    state::ship::{Object, Skill, Tile},
     Error,
 };
 
@@ -57,16 +69,143 @@ pub struct ObjectClassId {
 pub struct ObjectClass {
     pub friendly_name: String,
     pub uvs: Rect,
+    /// Sprites to show instead of `uvs` depending on the state of the object, picked using
+    /// `ObjectBehavior::frame_progress`. Only used if the class has a behavior.
+    pub frames: Vec<Rect>,
+    /// Picks the sprite based on neighboring objects of the same class, instead of `uvs` and
+    /// `frames`. Falls back to those if there's no sprite for a combination of neighbors.
+    pub autotile: Option<Autotile>,
+    /// Puts out fires near objects of this class.
+    pub extinguisher: Option<Extinguisher>,
+
+    /// How many seconds of work at normal skill it takes to build this.
+    pub build_work: f32,
+    /// What a unit needs to be able to build this, if anything.
+    pub build_requirement: Option<SkillRequirement>,
+    /// How much damage objects of this class can take before they're destroyed.
+    pub max_health: f32,
 
     pub behavior: Option<Box<ObjectBehavior>>,
 }
 
+impl ObjectClass {
+    /// Gets the sprite to show for an object of this class, depending on its state.
+    pub fn object_uvs(&self, object: &Object) -> Rect {
+        if let Some(ref behavior) = self.behavior {
+            if !self.frames.is_empty() {
+                let progress = behavior.frame_progress(object).max(0.0).min(1.0);
+                let index = (progress * (self.frames.len() - 1) as f32).round() as usize;
+                return self.frames[index]
+            }
+        }
+
+        self.uvs
+    }
+
+    /// Gets the sprite to show for an object of this class placed in the tiles, depending on its
+    /// state and its neighbors.
+    pub fn tile_uvs(&self, object: &Object, tiles: &Tiles<Tile>, position: Point2<i32>) -> Rect {
+        self.autotile.as_ref()
+            .and_then(|autotile| autotile.sprite(tiles, position, object.class))
+            .unwrap_or_else(|| self.object_uvs(object))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SkillRequirement {
+    pub skill: Skill,
+    pub level: u32,
+}
+
 pub trait ObjectBehavior {
     fn walkable(&self) -> Walkable;
+    fn walk_cost(&self) -> WalkCost;
 
     fn initialize(&self, object: &mut Object);
     fn update(&self, object: &mut Object, delta: f32);
 
     fn is_open(&self, object: &Object) -> bool;
     fn work_open(&self, object: &mut Object, delta: f32) -> bool;
+
+    /// How far along its frames the object is, from 0.0 for the first to 1.0 for the last.
+    fn frame_progress(&self, object: &Object) -> f32;
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+        ggez::graphics::{Rect},
+
+        object_class::{
+            ObjectClasses, ObjectClass, Autotile, AutotileNeighbors, DoorObjectBehavior,
+            NORTH, EAST, SOUTH, WEST,
+        },
+        state::ship::{Ship},
+        test_utils::{self, WALL},
+    };
+
+    /// A different sprite for every index, so tests can tell which one was picked.
+    fn sprites(amount: usize) -> Vec<Rect> {
+        (0..amount).map(|i| Rect::new(i as f32, 0.0, 1.0, 1.0)).collect()
+    }
+
+    #[test]
+    fn door_frame_follows_openness() {
+        let class = ObjectClass {
+            frames: sprites(3),
+            behavior: Some(Box::new(DoorObjectBehavior)),
+            .. test_utils::class("Door", None)
+        };
+        let mut object_classes = ObjectClasses::new();
+        let id = object_classes.register(class);
+        let class = object_classes.get(id).unwrap();
+        let behavior = class.behavior.as_ref().unwrap();
+
+        let mut door = object_classes.create_object(id).unwrap();
+        assert_eq!(class.object_uvs(&door), class.frames[0]);
+
+        // Halfway through opening shows the middle frame, fully open shows the last
+        behavior.work_open(&mut door, 0.125);
+        assert_eq!(class.object_uvs(&door), class.frames[1]);
+        behavior.work_open(&mut door, 0.125);
+        assert!(behavior.is_open(&door));
+        assert_eq!(class.object_uvs(&door), class.frames[2]);
+
+        // Closing again goes back through the frames, after the door's cooldown
+        behavior.update(&mut door, 0.9);
+        assert_eq!(class.object_uvs(&door), class.frames[2]);
+        behavior.update(&mut door, 0.1);
+        behavior.update(&mut door, 0.15);
+        assert_eq!(class.object_uvs(&door), class.frames[1]);
+        behavior.update(&mut door, 0.25);
+        assert_eq!(class.object_uvs(&door), class.frames[0]);
+    }
+
+    #[test]
+    fn autotile_wall_picks_sprite_by_neighbors() {
+        let mut object_classes = ObjectClasses::new();
+        object_classes.register(ObjectClass {
+            autotile: Some(Autotile {
+                neighbors: AutotileNeighbors::Straight,
+                sprites: sprites(16),
+            }),
+            .. test_utils::class("Wall", None)
+        });
+        let class = object_classes.get(WALL).unwrap();
+
+        let mut ship = Ship::empty(Vector2::new(5, 5));
+        for &(x, y) in &[(1, 2), (2, 2), (3, 2), (2, 3)] {
+            test_utils::place(&mut ship, &object_classes, Point2::new(x, y), WALL);
+        }
+        let uvs = |x, y| {
+            let object = ship.tiles.get(Point2::new(x, y)).unwrap().object.as_ref().unwrap();
+            class.tile_uvs(object, &ship.tiles, Point2::new(x, y))
+        };
+
+        let sprites = &class.autotile.as_ref().unwrap().sprites;
+        assert_eq!(uvs(2, 2), sprites[NORTH | EAST | WEST]);
+        assert_eq!(uvs(1, 2), sprites[EAST]);
+        assert_eq!(uvs(2, 3), sprites[SOUTH]);
+    }
 }
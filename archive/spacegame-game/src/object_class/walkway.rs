@@ -0,0 +1,42 @@
+use {
+    object_class::{ObjectBehavior},
+    pathfinding::{Walkable, WalkCost},
+    state::ship::{Object},
+};
+
+/// An object that can always be walked over, but changes how long that takes, for example
+/// corridors that are faster to walk through.
+pub struct WalkwayObjectBehavior {
+    pub walk_multiplier: f32,
+}
+
+impl ObjectBehavior for WalkwayObjectBehavior {
+    fn walkable(&self) -> Walkable {
+        Walkable::Always
+    }
+
+    fn walk_cost(&self) -> WalkCost {
+        WalkCost {
+            multiplier: self.walk_multiplier,
+            delay: 0.0,
+        }
+    }
+
+    fn initialize(&self, _object: &mut Object) {
+    }
+
+    fn update(&self, _object: &mut Object, _delta: f32) {
+    }
+
+    fn is_open(&self, _object: &Object) -> bool {
+        true
+    }
+
+    fn work_open(&self, _object: &mut Object, _delta: f32) -> bool {
+        true
+    }
+
+    fn frame_progress(&self, _object: &Object) -> f32 {
+        0.0
+    }
+}
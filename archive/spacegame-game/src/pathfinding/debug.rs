@@ -0,0 +1,16 @@
+use {
+    cgmath::{Point2},
+};
+
+/// What a search that didn't find a path looked at, to be able to see why it failed.
+#[derive(Debug, Clone)]
+pub struct FailedSearch {
+    pub start: Point2<i32>,
+    pub goal: Point2<i32>,
+    pub goal_inclusive: bool,
+    /// All nodes the search expanded, in the order it expanded them.
+    pub explored: Vec<Point2<i32>>,
+    /// Diagonal moves from a node to a neighbor that were skipped because they would cut through
+    /// a hard corner.
+    pub corner_exclusions: Vec<(Point2<i32>, Point2<i32>)>,
+}
@@ -0,0 +1,141 @@
+use {
+    std::{
+        cmp::{Reverse},
+        collections::{BinaryHeap},
+    },
+
+    cgmath::{Point2, Vector2},
+
+    lagato::{grid::{Tiles}},
+
+    object_class::{ObjectClasses},
+    pathfinding::{Costs, neighbors},
+    state::ship::{Tile},
+};
+
+/// The cost to reach a destination from every tile, along with which way to go from each tile to
+/// get there. Calculated once, any amount of units can use it to find their way to the
+/// destination.
+pub struct FlowField {
+    goal: Point2<i32>,
+    goal_inclusive: bool,
+    seconds_per_unit: f32,
+    size: Vector2<i32>,
+    nodes: Vec<Option<FlowNode>>,
+}
+
+impl FlowField {
+    pub fn new(
+        goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Self {
+        let costs = Costs::new(seconds_per_unit, object_classes);
+        let mut field = FlowField {
+            goal,
+            goal_inclusive,
+            seconds_per_unit,
+            size: tiles.size(),
+            nodes: vec!(None; (tiles.size().x * tiles.size().y) as usize),
+        };
+
+        // Search outwards from the goal until every tile we can reach from it has a cost, this
+        // is the same as the reverse search find_path does but without a single start
+        let mut open = BinaryHeap::new();
+        if tiles.is_in_bounds(goal) {
+            field.set_node(goal, FlowNode { cost: 0, next: goal });
+            open.push(Reverse((0, goal.x, goal.y)));
+        }
+
+        while let Some(Reverse((cost, x, y))) = open.pop() {
+            let node = Point2::new(x, y);
+            if field.node_at(node).map(|n| n.cost) != Some(cost) {
+                // We already found a cheaper way to this node
+                continue
+            }
+
+            for (neighbor, step) in neighbors(
+                node, goal, goal, goal_inclusive, &costs, tiles, object_classes,
+            ) {
+                let neighbor_cost = cost + step;
+                if !tiles.is_in_bounds(neighbor) ||
+                   field.node_at(neighbor).map(|n| n.cost <= neighbor_cost).unwrap_or(false) {
+                    continue
+                }
+
+                field.set_node(neighbor, FlowNode { cost: neighbor_cost, next: node });
+                open.push(Reverse((neighbor_cost, neighbor.x, neighbor.y)));
+            }
+        }
+
+        field
+    }
+
+    pub fn goal(&self) -> Point2<i32> {
+        self.goal
+    }
+
+    /// Returns true if there's a way to the goal from the tile.
+    pub fn can_reach_from(&self, position: Point2<i32>) -> bool {
+        self.node_at(position).is_some()
+    }
+
+    /// Follows the field from the start to the goal, returns None if the goal can't be reached
+    /// from there. The path is in the same format as `find_path`.
+    pub fn path_from(
+        &self, start: Point2<i32>, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        let mut path = vec!(start);
+
+        // If we're standing somewhere blocked we still want to be able to move off it, so pick
+        // the best tile to step onto
+        let mut current = if self.can_reach_from(start) {
+            start
+        } else {
+            let costs = Costs::new(self.seconds_per_unit, object_classes);
+            let next = neighbors(
+                start, start, self.goal, self.goal_inclusive, &costs, tiles, object_classes,
+            ).into_iter()
+                .filter_map(|(neighbor, step)| self.node_at(neighbor)
+                    .map(|node| (node.cost + step, neighbor)))
+                .min_by_key(|&(cost, neighbor)| (cost, neighbor.x, neighbor.y))?
+                .1;
+            path.push(next);
+            next
+        };
+
+        while current != self.goal {
+            current = self.node_at(current)?.next;
+            path.push(current);
+        }
+
+        // Path following wants the path in reverse, goal first
+        path.reverse();
+
+        // If we don't need the goal, remove it
+        if !self.goal_inclusive {
+            path.remove(0);
+        }
+
+        Some(path)
+    }
+
+    fn node_at(&self, position: Point2<i32>) -> Option<FlowNode> {
+        if position.x < 0 || position.y < 0 ||
+           position.x >= self.size.x || position.y >= self.size.y {
+            return None
+        }
+
+        self.nodes[(position.x + position.y * self.size.x) as usize]
+    }
+
+    fn set_node(&mut self, position: Point2<i32>, node: FlowNode) {
+        let index = (position.x + position.y * self.size.x) as usize;
+        self.nodes[index] = Some(node);
+    }
+}
+
+#[derive(Copy, Clone)]
+struct FlowNode {
+    cost: i32,
+    next: Point2<i32>,
+}
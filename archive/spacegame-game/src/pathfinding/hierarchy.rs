@@ -0,0 +1,661 @@
+use {
+    cgmath::{Point2, Vector2},
+    metrohash::{MetroHashMap},
+    pathfindingc::{astar},
+
+    lagato::{grid::{Tiles}},
+
+    object_class::{ObjectClasses},
+    pathfinding::{self, Costs, WalkCost, COST_MULTIPLIER, neighbors, heuristic, is_walkable},
+    state::ship::{Tile},
+    Error,
+};
+
+const CLUSTER_SIZE: i32 = 10;
+
+/// A two-level graph over the tiles for finding long paths quickly. The tiles are split up into
+/// clusters, walkable openings between clusters become portals, and the costs of moving between
+/// portals within a cluster are cached for both directions. Only clusters around changed tiles get
+/// rebuilt.
+pub struct PathHierarchy {
+    clusters_size: Vector2<i32>,
+    tiles_size: Vector2<i32>,
+
+    clusters: Vec<Cluster>,
+    entrances: MetroHashMap<(Point2<i32>, Axis), Vec<Entrance>>,
+    links: MetroHashMap<Point2<i32>, Vec<(Point2<i32>, EdgeCost)>>,
+}
+
+impl PathHierarchy {
+    pub fn new() -> Self {
+        PathHierarchy {
+            clusters_size: Vector2::new(0, 0),
+            tiles_size: Vector2::new(0, 0),
+
+            clusters: Vec::new(),
+            entrances: MetroHashMap::default(),
+            links: MetroHashMap::default(),
+        }
+    }
+
+    /// Rebuilds the entire hierarchy from scratch.
+    pub fn rebuild(
+        &mut self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        self.tiles_size = tiles.size();
+        self.clusters_size = Vector2::new(
+            (self.tiles_size.x + CLUSTER_SIZE - 1) / CLUSTER_SIZE,
+            (self.tiles_size.y + CLUSTER_SIZE - 1) / CLUSTER_SIZE,
+        );
+
+        let amount = (self.clusters_size.x * self.clusters_size.y) as usize;
+        self.clusters = (0..amount).map(|_| Cluster::new()).collect();
+        self.entrances.clear();
+        self.links.clear();
+
+        let mut all = Vec::new();
+        for y in 0..self.clusters_size.y {
+            for x in 0..self.clusters_size.x {
+                all.push(Point2::new(x, y));
+            }
+        }
+
+        self.rebuild_clusters(&all, tiles, object_classes)
+    }
+
+    /// Rebuilds the parts of the hierarchy affected by the given changed tiles.
+    pub fn invalidate(
+        &mut self, changed: &[Point2<i32>], tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        let mut dirty = Vec::new();
+        for position in changed {
+            let cluster = self.cluster_of(*position);
+            if !dirty.contains(&cluster) {
+                dirty.push(cluster);
+            }
+        }
+
+        self.rebuild_clusters(&dirty, tiles, object_classes)
+    }
+
+    /// Finds a path to the goal, returns None if no path could be found. The path is in the same
+    /// format as `pathfinding::find_path`.
+    pub fn find_path(
+        &self,
+        start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        // The hierarchy can't help us with these, so just do a regular search. Paths starting
+        // or ending on a blocked tile may need to leave through a neighboring cluster, which the
+        // portals don't account for.
+        let is_open = |position| is_walkable(tiles.get(position), object_classes).unwrap();
+        if start == goal || !is_open(start) || !is_open(goal) {
+            return pathfinding::find_path(
+                start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )
+        }
+
+        // The cached routes between portals are found at one second per unit, for other speeds
+        // they're a close approximation of the shortest ones, but their costs are exact
+        let costs = Costs::new(seconds_per_unit, object_classes);
+
+        // Like the regular search we search in reverse, so connect the goal to its cluster's
+        // portals, and the start's cluster's portals to the start
+        let goal_cluster = self.cluster_of(goal);
+        let start_cluster = self.cluster_of(start);
+
+        let mut goal_edges = Vec::new();
+        if goal_cluster == start_cluster {
+            if let Some((_, cost)) = self.local_search(
+                goal, start, goal_inclusive, &costs, tiles, object_classes,
+            ) {
+                goal_edges.push((start, cost));
+            }
+        }
+        for portal in &self.cluster(goal_cluster).portals {
+            if let Some((_, cost)) = self.local_search(
+                goal, *portal, goal_inclusive, &costs, tiles, object_classes,
+            ) {
+                goal_edges.push((*portal, cost));
+            }
+        }
+
+        let mut start_edges = MetroHashMap::default();
+        for portal in &self.cluster(start_cluster).portals {
+            if let Some((_, cost)) = self.local_search(
+                *portal, start, true, &costs, tiles, object_classes,
+            ) {
+                start_edges.insert(*portal, cost);
+            }
+        }
+
+        // Find the route through the portals
+        let (route, _cost) = astar::astar(
+            &goal,
+            |node| self.abstract_neighbors(
+                *node, start, goal, seconds_per_unit, &goal_edges, &start_edges,
+            ),
+            |node| heuristic(*node, start, &costs),
+            |node| *node == start,
+        )?;
+
+        // Fill in the actual tiles between the portals, if the hierarchy is out of date with the
+        // tiles this can fail, in which case we fall back to a regular search
+        let path = self.refine(&route, goal, goal_inclusive, &costs, tiles, object_classes);
+        if let Some(mut path) = path {
+            // If we don't need the goal, remove it
+            if !goal_inclusive {
+                path.remove(0);
+            }
+
+            Some(path)
+        } else {
+            pathfinding::find_path(
+                start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )
+        }
+    }
+
+    fn abstract_neighbors(
+        &self,
+        node: Point2<i32>, start: Point2<i32>, goal: Point2<i32>, seconds_per_unit: f32,
+        goal_edges: &[(Point2<i32>, i32)], start_edges: &MetroHashMap<Point2<i32>, i32>,
+    ) -> Vec<(Point2<i32>, i32)> {
+        let mut neighbors = Vec::new();
+
+        if node == goal {
+            neighbors.extend_from_slice(goal_edges);
+        }
+
+        let cached = self.links.get(&node).into_iter()
+            .chain(self.cluster(self.cluster_of(node)).edges.get(&node))
+            .flat_map(|edges| edges.iter());
+        for &(neighbor, cost) in cached {
+            neighbors.push((neighbor, cost.cost(seconds_per_unit)));
+        }
+
+        if let Some(cost) = start_edges.get(&node) {
+            neighbors.push((start, *cost));
+        }
+
+        neighbors
+    }
+
+    fn refine(
+        &self,
+        route: &[Point2<i32>], goal: Point2<i32>, goal_inclusive: bool, costs: &Costs,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        let mut path = vec!(goal);
+
+        for pair in route.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+
+            if self.cluster_of(from) != self.cluster_of(to) {
+                // Portals in different clusters are always right next to each other
+                path.push(to);
+            } else {
+                // Only the goal itself may be treated as not inclusive
+                let inclusive = goal_inclusive || from != goal;
+                let (segment, _) = self.local_search(
+                    from, to, inclusive, costs, tiles, object_classes,
+                )?;
+                path.extend(segment.into_iter().skip(1));
+            }
+        }
+
+        Some(path)
+    }
+
+    /// Searches from one tile to another without leaving the cluster they're both in.
+    fn local_search(
+        &self,
+        from: Point2<i32>, to: Point2<i32>, from_inclusive: bool, costs: &Costs,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<(Vec<Point2<i32>>, i32)> {
+        let (min, max) = self.cluster_bounds(self.cluster_of(from));
+
+        astar::astar(
+            &from,
+            |node| {
+                let mut neighbors = neighbors(
+                    *node, to, from, from_inclusive, costs, tiles, object_classes,
+                );
+                neighbors.retain(|&(neighbor, _)|
+                    neighbor.x >= min.x && neighbor.y >= min.y &&
+                    neighbor.x < max.x && neighbor.y < max.y
+                );
+                neighbors
+            },
+            |node| heuristic(*node, to, costs),
+            |node| *node == to,
+        )
+    }
+
+    fn rebuild_clusters(
+        &mut self,
+        dirty: &[Point2<i32>], tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        // Find the entrances on all borders of the dirty clusters, changing these can affect the
+        // portals of the neighboring clusters as well
+        let mut affected = Vec::new();
+        for cluster in dirty {
+            let borders = [
+                (*cluster, Axis::X),
+                (*cluster, Axis::Y),
+                (*cluster - Axis::X.offset(), Axis::X),
+                (*cluster - Axis::Y.offset(), Axis::Y),
+            ];
+
+            for &(border_cluster, axis) in &borders {
+                if !self.is_cluster_in_bounds(border_cluster) {
+                    continue
+                }
+
+                // The links between the clusters are only for the border's entrances, so they
+                // get replaced along with them
+                let entrances = self.find_entrances(border_cluster, axis, tiles, object_classes)?;
+                if let Some(old_entrances) = self.entrances.remove(&(border_cluster, axis)) {
+                    self.remove_links(&old_entrances);
+                }
+                self.add_links(&entrances, tiles, object_classes)?;
+                self.entrances.insert((border_cluster, axis), entrances);
+
+                for affected_cluster in &[border_cluster, border_cluster + axis.offset()] {
+                    if self.is_cluster_in_bounds(*affected_cluster) &&
+                       !affected.contains(affected_cluster) {
+                        affected.push(*affected_cluster);
+                    }
+                }
+            }
+
+            if !affected.contains(cluster) {
+                affected.push(*cluster);
+            }
+        }
+
+        // Now recalculate the costs between the portals for every cluster that may have changed.
+        // Entering a tile can have a delay, so moving between two portals doesn't have to cost
+        // the same both ways, and each direction gets searched on its own.
+        let costs = Costs::new(1.0, object_classes);
+        for cluster in affected {
+            let portals = self.find_portals(cluster);
+
+            let mut edges: MetroHashMap<Point2<i32>, Vec<(Point2<i32>, EdgeCost)>> =
+                MetroHashMap::default();
+            for from in &portals {
+                for to in &portals {
+                    if from == to {
+                        continue
+                    }
+
+                    if let Some((path, _)) = self.local_search(
+                        *from, *to, true, &costs, tiles, object_classes,
+                    ) {
+                        let cost = EdgeCost::of_path(&path, tiles, object_classes)?;
+                        edges.entry(*from).or_insert_with(Vec::new).push((*to, cost));
+                    }
+                }
+            }
+
+            let index = self.cluster_index(cluster);
+            self.clusters[index] = Cluster {
+                portals,
+                edges,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Links the portals on both sides of the entrances, like everything else these are searched
+    /// in reverse so the cost is for moving from the link to the key.
+    fn add_links(
+        &mut self,
+        entrances: &[Entrance], tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        for entrance in entrances {
+            let inner = tiles.get(entrance.inner);
+            let outer = tiles.get(entrance.outer);
+            let inner_cost = WalkCost::from_tile_res(inner, object_classes)?;
+            let outer_cost = WalkCost::from_tile_res(outer, object_classes)?;
+
+            self.links.entry(entrance.inner).or_insert_with(Vec::new)
+                .push((entrance.outer, EdgeCost::step(1.0, &outer_cost, &inner_cost)));
+            self.links.entry(entrance.outer).or_insert_with(Vec::new)
+                .push((entrance.inner, EdgeCost::step(1.0, &inner_cost, &outer_cost)));
+        }
+
+        Ok(())
+    }
+
+    /// Removes the links added for the entrances, a portal can be on more than one border so
+    /// only the links across these entrances are removed.
+    fn remove_links(&mut self, entrances: &[Entrance]) {
+        for entrance in entrances {
+            let sides = [(entrance.inner, entrance.outer), (entrance.outer, entrance.inner)];
+            for &(portal, other) in &sides {
+                let now_empty = if let Some(links) = self.links.get_mut(&portal) {
+                    links.retain(|&(neighbor, _)| neighbor != other);
+                    links.is_empty()
+                } else {
+                    false
+                };
+
+                if now_empty {
+                    self.links.remove(&portal);
+                }
+            }
+        }
+    }
+
+    /// Finds the entrances on the border between a cluster and the next cluster on the axis.
+    fn find_entrances(
+        &self,
+        cluster: Point2<i32>, axis: Axis, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<Vec<Entrance>, Error> {
+        let mut entrances = Vec::new();
+        if !self.is_cluster_in_bounds(cluster + axis.offset()) {
+            return Ok(entrances)
+        }
+
+        // Every run of walkable tiles along the border gets a single entrance in the middle
+        let (min, max) = self.cluster_bounds(cluster);
+        let length = match axis {
+            Axis::X => max.y - min.y,
+            Axis::Y => max.x - min.x,
+        };
+
+        let mut run = Vec::new();
+        for i in 0..length {
+            let entrance = match axis {
+                Axis::X => Entrance {
+                    inner: Point2::new(max.x - 1, min.y + i),
+                    outer: Point2::new(max.x, min.y + i),
+                },
+                Axis::Y => Entrance {
+                    inner: Point2::new(min.x + i, max.y - 1),
+                    outer: Point2::new(min.x + i, max.y),
+                },
+            };
+
+            if is_walkable(tiles.get(entrance.inner), object_classes)? &&
+               is_walkable(tiles.get(entrance.outer), object_classes)? {
+                run.push(entrance);
+            } else if !run.is_empty() {
+                entrances.push(run[run.len() / 2]);
+                run.clear();
+            }
+        }
+        if !run.is_empty() {
+            entrances.push(run[run.len() / 2]);
+        }
+
+        Ok(entrances)
+    }
+
+    fn find_portals(&self, cluster: Point2<i32>) -> Vec<Point2<i32>> {
+        let mut portals = Vec::new();
+
+        let borders = [
+            ((cluster, Axis::X), true),
+            ((cluster, Axis::Y), true),
+            ((cluster - Axis::X.offset(), Axis::X), false),
+            ((cluster - Axis::Y.offset(), Axis::Y), false),
+        ];
+        for &(key, inner) in &borders {
+            if let Some(entrances) = self.entrances.get(&key) {
+                for entrance in entrances {
+                    let portal = if inner { entrance.inner } else { entrance.outer };
+                    if !portals.contains(&portal) {
+                        portals.push(portal);
+                    }
+                }
+            }
+        }
+
+        portals
+    }
+
+    fn cluster(&self, cluster: Point2<i32>) -> &Cluster {
+        &self.clusters[self.cluster_index(cluster)]
+    }
+
+    fn cluster_index(&self, cluster: Point2<i32>) -> usize {
+        (cluster.x + cluster.y * self.clusters_size.x) as usize
+    }
+
+    fn cluster_of(&self, position: Point2<i32>) -> Point2<i32> {
+        Point2::new(position.x / CLUSTER_SIZE, position.y / CLUSTER_SIZE)
+    }
+
+    /// Calculates the start and exclusive end tile positions of a cluster.
+    fn cluster_bounds(&self, cluster: Point2<i32>) -> (Point2<i32>, Point2<i32>) {
+        let min = Point2::new(cluster.x * CLUSTER_SIZE, cluster.y * CLUSTER_SIZE);
+        let max = Point2::new(
+            (min.x + CLUSTER_SIZE).min(self.tiles_size.x),
+            (min.y + CLUSTER_SIZE).min(self.tiles_size.y),
+        );
+        (min, max)
+    }
+
+    fn is_cluster_in_bounds(&self, cluster: Point2<i32>) -> bool {
+        cluster.x >= 0 && cluster.y >= 0 &&
+        cluster.x < self.clusters_size.x && cluster.y < self.clusters_size.y
+    }
+}
+
+struct Cluster {
+    portals: Vec<Point2<i32>>,
+    edges: MetroHashMap<Point2<i32>, Vec<(Point2<i32>, EdgeCost)>>,
+}
+
+impl Cluster {
+    fn new() -> Self {
+        Cluster {
+            portals: Vec::new(),
+            edges: MetroHashMap::default(),
+        }
+    }
+}
+
+/// The cost of a cached edge, kept apart into time spent walking and time spent waiting so it can
+/// be scaled to any walking speed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct EdgeCost {
+    /// Distance walked, weighted by the walk cost multipliers of the tiles.
+    walk: f32,
+    /// Seconds spent waiting before entering tiles.
+    delay: f32,
+}
+
+impl EdgeCost {
+    /// The cost of a single step, like `pathfinding::step_cost` but without a speed yet.
+    fn step(distance: f32, from: &WalkCost, to: &WalkCost) -> Self {
+        EdgeCost {
+            walk: distance * (from.multiplier + to.multiplier) * 0.5,
+            delay: to.delay,
+        }
+    }
+
+    /// Adds up the steps of a path found by searching in reverse, so every step is walked from
+    /// the later tile to the earlier one.
+    fn of_path(
+        path: &[Point2<i32>], tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<Self, Error> {
+        let mut total = EdgeCost { walk: 0.0, delay: 0.0 };
+        for pair in path.windows(2) {
+            let (to, from) = (pair[0], pair[1]);
+            let distance = if to.x == from.x || to.y == from.y { 1.0 } else { f32::sqrt(2.0) };
+            let step = EdgeCost::step(
+                distance,
+                &WalkCost::from_tile_res(tiles.get(from), object_classes)?,
+                &WalkCost::from_tile_res(tiles.get(to), object_classes)?,
+            );
+            total.walk += step.walk;
+            total.delay += step.delay;
+        }
+
+        Ok(total)
+    }
+
+    fn cost(self, seconds_per_unit: f32) -> i32 {
+        ((self.walk * seconds_per_unit + self.delay) * COST_MULTIPLIER) as i32
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    fn offset(self) -> Vector2<i32> {
+        match self {
+            Axis::X => Vector2::new(1, 0),
+            Axis::Y => Vector2::new(0, 1),
+        }
+    }
+}
+
+/// A pair of walkable tiles on either side of a border, inner being in the cluster with the lower
+/// coordinate.
+#[derive(Copy, Clone)]
+struct Entrance {
+    inner: Point2<i32>,
+    outer: Point2<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+        metrohash::{MetroHashMap},
+
+        lagato::{grid::{Tiles}},
+
+        object_class::{ObjectClasses},
+        pathfinding::{self, Walkable, hierarchy::{PathHierarchy, EdgeCost}},
+        state::ship::{Ship, Tile},
+        test_utils::{self, DOOR, WALL},
+    };
+
+    type Edges = Vec<(Point2<i32>, EdgeCost)>;
+
+    /// A ship four clusters wide and three high, with a long wall down the middle that can only
+    /// be passed at the bottom.
+    fn walled_ship() -> (Ship, ObjectClasses) {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(40, 30));
+        for y in 1..25 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(20, y), WALL);
+        }
+        (ship, object_classes)
+    }
+
+    /// Checks that the path goes from the start through walkable neighboring tiles, and gives
+    /// back what walking it costs.
+    fn check_path(
+        path: &[Point2<i32>], start: Point2<i32>,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> i32 {
+        assert_eq!(path.last(), Some(&start));
+        for pair in path.windows(2) {
+            let offset = pair[0] - pair[1];
+            assert!(offset.x.abs() <= 1 && offset.y.abs() <= 1 && pair[0] != pair[1]);
+            let walkable = Walkable::from_tile_res(tiles.get(pair[0]), object_classes).unwrap();
+            assert!(walkable != Walkable::Never, "Path goes through {:?}", pair[0]);
+        }
+
+        EdgeCost::of_path(path, tiles, object_classes).unwrap().cost(1.0)
+    }
+
+    fn sorted(edges: &MetroHashMap<Point2<i32>, Edges>) -> Vec<(Point2<i32>, Edges)> {
+        let mut sorted: Vec<_> = edges.iter()
+            .map(|(portal, edges)| {
+                let mut edges = edges.clone();
+                edges.sort_by_key(|&(neighbor, _)| (neighbor.x, neighbor.y));
+                (*portal, edges)
+            })
+            .collect();
+        sorted.sort_by_key(|&(portal, _)| (portal.x, portal.y));
+        sorted
+    }
+
+    #[test]
+    fn finds_paths_as_good_as_a_full_search() {
+        let (ship, object_classes) = walled_ship();
+        let mut hierarchy = PathHierarchy::new();
+        hierarchy.rebuild(&ship.tiles, &object_classes).unwrap();
+
+        let (start, goal) = (Point2::new(3, 3), Point2::new(36, 3));
+        let path = hierarchy.find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .unwrap();
+        let full = pathfinding::find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .unwrap();
+
+        assert_eq!(path[0], goal);
+        let cost = check_path(&path, start, &ship.tiles, &object_classes);
+        let full_cost = check_path(&full, start, &ship.tiles, &object_classes);
+        assert!(cost >= full_cost);
+        assert!(cost as f32 <= full_cost as f32 * 1.1, "{} vs {}", cost, full_cost);
+    }
+
+    #[test]
+    fn invalidating_matches_a_rebuild() {
+        let (mut ship, object_classes) = walled_ship();
+        let mut hierarchy = PathHierarchy::new();
+        hierarchy.rebuild(&ship.tiles, &object_classes).unwrap();
+
+        // Close the gap at the bottom and open one at the top instead
+        let mut changed = Vec::new();
+        for y in 25..29 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(20, y), WALL);
+            changed.push(Point2::new(20, y));
+        }
+        ship.tiles.get_mut(Point2::new(20, 2)).unwrap().object = None;
+        changed.push(Point2::new(20, 2));
+        hierarchy.invalidate(&changed, &ship.tiles, &object_classes).unwrap();
+
+        let (start, goal) = (Point2::new(3, 20), Point2::new(36, 20));
+        let path = hierarchy.find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .unwrap();
+        check_path(&path, start, &ship.tiles, &object_classes);
+        assert!(path.contains(&Point2::new(20, 2)));
+
+        let mut rebuilt = PathHierarchy::new();
+        rebuilt.rebuild(&ship.tiles, &object_classes).unwrap();
+        assert_eq!(sorted(&hierarchy.links), sorted(&rebuilt.links));
+        for (cluster, rebuilt_cluster) in hierarchy.clusters.iter().zip(&rebuilt.clusters) {
+            assert_eq!(sorted(&cluster.edges), sorted(&rebuilt_cluster.edges));
+        }
+    }
+
+    #[test]
+    fn edges_cost_more_going_through_a_door() {
+        // Three clusters in a row, the middle one has a portal on each side with a door on the
+        // right one
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(30, 10));
+        let (left, right) = (Point2::new(10, 5), Point2::new(19, 5));
+        test_utils::place(&mut ship, &object_classes, right, DOOR);
+
+        let mut hierarchy = PathHierarchy::new();
+        hierarchy.rebuild(&ship.tiles, &object_classes).unwrap();
+
+        // Edges are searched in reverse, the edge from the right portal is walked into the door
+        let cluster = hierarchy.cluster(Point2::new(1, 0));
+        let edge_cost = |from, to| cluster.edges[&from].iter()
+            .find(|&&(neighbor, _)| neighbor == to)
+            .map(|&(_, cost)| cost)
+            .unwrap();
+        let into_door = edge_cost(right, left);
+        let out_of_door = edge_cost(left, right);
+
+        assert!(into_door.delay > 0.0);
+        assert_eq!(out_of_door.delay, 0.0);
+        assert!(into_door.cost(1.0) > out_of_door.cost(1.0));
+        assert!(out_of_door.cost(2.0) > out_of_door.cost(1.0));
+    }
+}
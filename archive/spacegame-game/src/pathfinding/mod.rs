@@ -1,5 +1,25 @@
+mod debug;
+mod flow_field;
+mod hierarchy;
+mod regions;
+mod requests;
+
+pub use self::{
+    debug::{FailedSearch},
+    flow_field::{FlowField},
+    hierarchy::{PathHierarchy},
+    regions::{Regions, RegionId},
+    requests::{PathRequests, PathRequestId, PathRequest},
+};
+
 use {
+    std::{
+        cell::{RefCell, Ref},
+        rc::{Rc},
+    },
+
     cgmath::{Point2},
+    metrohash::{MetroHashMap},
     pathfindingc::{astar},
 
     lagato::{self, grid::{Tiles}},
@@ -11,22 +31,229 @@ use {
 
 const COST_MULTIPLIER: f32 = 100.0;
 
+/// Pathfinding data for a ship, kept up to date with its tiles.
+pub struct Pathfinder {
+    walkable: Vec<(Walkable, WalkCost)>,
+    hierarchy: PathHierarchy,
+    regions: Regions,
+    // Flow fields are created on demand while pathfinding, so they're cached behind a RefCell,
+    // f32 can't be hashed so the seconds per unit are kept as their bits
+    flow_fields: RefCell<MetroHashMap<(Point2<i32>, bool, u32), Rc<FlowField>>>,
+    // Kept to be able to see why things can't be reached, searching doesn't need mutable access.
+    // Only recorded while debugging, keeping track of everything a search looks at isn't free.
+    debug: bool,
+    last_failed_search: RefCell<Option<FailedSearch>>,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Pathfinder {
+            walkable: Vec::new(),
+            hierarchy: PathHierarchy::new(),
+            regions: Regions::new(),
+            flow_fields: RefCell::new(MetroHashMap::default()),
+            debug: false,
+            last_failed_search: RefCell::new(None),
+        }
+    }
+
+    pub fn is_built(&self) -> bool {
+        !self.walkable.is_empty()
+    }
+
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// The last full search that didn't find a path, with what it looked at, for debugging.
+    pub fn last_failed_search(&self) -> Ref<Option<FailedSearch>> {
+        self.last_failed_search.borrow()
+    }
+
+    /// Sets if failed searches should be recorded, turning it off forgets the last one.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+        if !debug {
+            *self.last_failed_search.borrow_mut() = None;
+        }
+    }
+
+    /// Finds a path to the goal, returns None if no path could be found. The path is in the same
+    /// format as `find_path`.
+    pub fn find_path(
+        &self,
+        start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        if !self.is_built() {
+            return self.full_search(
+                start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )
+        }
+
+        // If they're not connected at all there's no need to search
+        if !self.regions.can_reach(start, goal, goal_inclusive) {
+            return self.record_unreachable(
+                start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )
+        }
+
+        // The hierarchy can miss some paths that squeeze past the edges of its clusters, if it
+        // does we know from the regions there still is a path so fall back to a full search
+        self.hierarchy.find_path(
+            start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+        ).or_else(|| self.full_search(
+            start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+        ))
+    }
+
+    /// Searches over all tiles, keeping what the search looked at if it fails while debugging.
+    fn full_search(
+        &self,
+        start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        let (path, failed) = search(
+            start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes, self.debug,
+        );
+        if failed.is_some() {
+            *self.last_failed_search.borrow_mut() = failed;
+        }
+        path
+    }
+
+    /// Gives back that there's no path, while debugging this still does the full search so what
+    /// it looked at can be seen.
+    fn record_unreachable(
+        &self,
+        start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        if self.debug {
+            self.full_search(start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the flow field to a destination, creating it if it doesn't exist yet. Flow fields are
+    /// kept until the tiles change, so any amount of units going to the same place only need one.
+    pub fn flow_field(
+        &self, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Rc<FlowField> {
+        self.flow_fields.borrow_mut()
+            .entry((goal, goal_inclusive, seconds_per_unit.to_bits()))
+            .or_insert_with(|| Rc::new(FlowField::new(
+                goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )))
+            .clone()
+    }
+
+    /// Checks if there already is a flow field to a destination, so finding paths there is cheap.
+    pub fn has_flow_field(
+        &self, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+    ) -> bool {
+        self.flow_fields.borrow()
+            .contains_key(&(goal, goal_inclusive, seconds_per_unit.to_bits()))
+    }
+
+    /// Finds a path to the goal using a shared flow field, returns None if no path could be found.
+    /// This is faster than `find_path` when many units are going to the same place. The path is
+    /// in the same format as `find_path`.
+    pub fn find_path_shared(
+        &self,
+        start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+        tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Option<Vec<Point2<i32>>> {
+        // If they're not connected at all there's no need to create a field
+        if self.is_built() && !self.regions.can_reach(start, goal, goal_inclusive) {
+            return self.record_unreachable(
+                start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes,
+            )
+        }
+
+        self.flow_field(goal, goal_inclusive, seconds_per_unit, tiles, object_classes)
+            .path_from(start, tiles, object_classes)
+    }
+
+    /// Updates the pathfinding data after the tiles have changed, returns the positions of all
+    /// tiles that changed walkability or walk cost.
+    pub fn handle_tiles_changed(
+        &mut self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<Vec<Point2<i32>>, Error> {
+        let mut walkable = Vec::new();
+        for position in tiles.iter_pos() {
+            walkable.push((
+                Walkable::from_tile_res(tiles.get(position), object_classes)?,
+                WalkCost::from_tile_res(tiles.get(position), object_classes)?,
+            ));
+        }
+
+        let changed = if walkable.len() != self.walkable.len() {
+            // We don't have anything to compare against yet, so everything changed
+            self.hierarchy.rebuild(tiles, object_classes)?;
+            tiles.iter_pos().collect()
+        } else {
+            let changed: Vec<_> = tiles.iter_pos()
+                .zip(self.walkable.iter().zip(walkable.iter()))
+                .filter(|&(_, (old, new))| old != new)
+                .map(|(position, _)| position)
+                .collect();
+            self.hierarchy.invalidate(&changed, tiles, object_classes)?;
+            changed
+        };
+
+        // A failed search may not fail anymore, and would show tiles as they were before
+        if !changed.is_empty() {
+            self.regions.rebuild(tiles, object_classes)?;
+            self.flow_fields.borrow_mut().clear();
+            *self.last_failed_search.borrow_mut() = None;
+        }
+
+        self.walkable = walkable;
+        Ok(changed)
+    }
+}
+
+impl Default for Pathfinder {
+    fn default() -> Self {
+        Pathfinder::new()
+    }
+}
+
 /// Finds a path to the goal, returns None if no path could be found.
 pub fn find_path(
     start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
     tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
 ) -> Option<Vec<Point2<i32>>> {
+    search(start, goal, goal_inclusive, seconds_per_unit, tiles, object_classes, false).0
+}
+
+/// Finds a path to the goal like `find_path`, if no path could be found and `record` is set this
+/// also gives back what the search looked at.
+fn search(
+    start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool, seconds_per_unit: f32,
+    tiles: &Tiles<Tile>, object_classes: &ObjectClasses, record: bool,
+) -> (Option<Vec<Point2<i32>>>, Option<FailedSearch>) {
     // Calculate some advance values relevant to pathfinding
-    let costs = Costs {
-        straight: seconds_per_unit,
-        diagonal: f32::sqrt(2.0) * seconds_per_unit,
-    };
+    let costs = Costs::new(seconds_per_unit, object_classes);
 
-    // Now do the actual pathfinding
-    // Keep in mind our path following wants the path in reverse, so we A* in reverse
+    // Now do the actual pathfinding, keeping track of what we're looking at along the way if
+    // asked to. Keep in mind our path following wants the path in reverse, so we A* in reverse
+    let mut explored = Vec::new();
+    let mut corner_exclusions = Vec::new();
     let result = astar::astar(
         &goal,
-        |node| neighbors(*node, start, goal, goal_inclusive, &costs, tiles, object_classes),
+        |node| {
+            if record {
+                explored.push(*node);
+            }
+            collect_neighbors(
+                *node, start, goal, goal_inclusive, &costs, tiles, object_classes,
+                if record { Some(&mut corner_exclusions) } else { None },
+            )
+        },
         |node| heuristic(*node, start, &costs),
         |node| *node == start,
     );
@@ -38,10 +265,18 @@ pub fn find_path(
         }
 
         // Everything's set for path following, return that we found a path
-        Some(path)
+        (Some(path), None)
+    } else if record {
+        // We didn't find a path, return that this is unreachable and what we looked at
+        (None, Some(FailedSearch {
+            start,
+            goal,
+            goal_inclusive,
+            explored,
+            corner_exclusions,
+        }))
     } else {
-        // We didn't find a path, return that this is unreachable
-        None
+        (None, None)
     }
 }
 
@@ -64,18 +299,75 @@ impl Walkable {
     }
 }
 
+/// How long it takes to walk over a tile.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct WalkCost {
+    /// How many times as long as a bare floor it takes to walk over the tile.
+    pub multiplier: f32,
+    /// How many seconds we need to wait before we can enter the tile, for example to open a door.
+    pub delay: f32,
+}
+
+impl WalkCost {
+    pub fn normal() -> Self {
+        WalkCost {
+            multiplier: 1.0,
+            delay: 0.0,
+        }
+    }
+
+    pub fn from_tile_res(
+        tile_res: Result<&Tile, lagato::grid::Error>, object_classes: &ObjectClasses
+    ) -> Result<WalkCost, Error> {
+        if let Ok(tile) = tile_res {
+            tile.walk_cost(object_classes)
+        } else {
+            Ok(WalkCost::normal())
+        }
+    }
+}
+
 struct Costs {
     // Can only use Ord cost, f32 isn't Ord
     straight: f32,
     diagonal: f32,
+    // Needed to keep the heuristic from overestimating on tiles faster than a bare floor
+    lowest_multiplier: f32,
+}
+
+impl Costs {
+    fn new(seconds_per_unit: f32, object_classes: &ObjectClasses) -> Self {
+        let lowest_multiplier = object_classes.entries().iter()
+            .filter_map(|class| class.behavior.as_ref())
+            .map(|behavior| behavior.walk_cost().multiplier)
+            .fold(1.0, f32::min);
+
+        Costs {
+            straight: seconds_per_unit,
+            diagonal: f32::sqrt(2.0) * seconds_per_unit,
+            lowest_multiplier,
+        }
+    }
 }
 
 fn neighbors(
     node: Point2<i32>, start: Point2<i32>, goal: Point2<i32>,
     goal_inclusive: bool, costs: &Costs,
     tiles: &Tiles<Tile>, object_classes: &ObjectClasses
+) -> Vec<(Point2<i32>, i32)> {
+    collect_neighbors(node, start, goal, goal_inclusive, costs, tiles, object_classes, None)
+}
+
+/// Finds the neighbors of a node like `neighbors`, optionally keeping track of the diagonal moves
+/// that were skipped because they would go through a hard corner.
+fn collect_neighbors(
+    node: Point2<i32>, start: Point2<i32>, goal: Point2<i32>,
+    goal_inclusive: bool, costs: &Costs,
+    tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    mut corner_exclusions: Option<&mut Vec<(Point2<i32>, Point2<i32>)>>,
 ) -> Vec<(Point2<i32>, i32)> {
     let mut neighbors = Vec::new();
+    let node_cost = WalkCost::from_tile_res(tiles.get(node), object_classes).unwrap();
 
     for y in node.y-1..node.y+2 {
         for x in node.x-1..node.x+2 {
@@ -97,13 +389,9 @@ fn neighbors(
                 }
             }
 
-            // TODO: The way walk cost is used in calculations is technically currently incorrect,
-            // because moving to this tile we only spend half of the time on this one and half on
-            // the previous one. Therefore, both should be taken into account.
-
             // Cost differ for straight and diagonal movement
-            let cost = if x == node.x || y == node.y {
-                (costs.straight * COST_MULTIPLIER) as i32
+            let distance_cost = if x == node.x || y == node.y {
+                costs.straight
             } else {
                 // Hard corner check is not needed if we don't actually need to move to it, which
                 // is the case if we're not goal inclusive and the node is the goal
@@ -111,14 +399,19 @@ fn neighbors(
                     // Make sure we're not moving through a hard corner
                     if !is_walkable(tiles.get(Point2::new(x, node.y)), object_classes).unwrap() ||
                        !is_walkable(tiles.get(Point2::new(node.x, y)), object_classes).unwrap() {
+                        if let Some(ref mut corner_exclusions) = corner_exclusions {
+                            corner_exclusions.push((node, neighbor));
+                        }
                         continue
                     }
                 }
 
-                (costs.diagonal * COST_MULTIPLIER) as i32
+                costs.diagonal
             };
 
-            neighbors.push((neighbor, cost));
+            let neighbor_cost = WalkCost::from_tile_res(tiles.get(neighbor), object_classes)
+                .unwrap();
+            neighbors.push((neighbor, step_cost(distance_cost, &neighbor_cost, &node_cost)));
         }
     }
 
@@ -131,11 +424,81 @@ fn is_walkable(
     Ok(Walkable::from_tile_res(tile_res, object_classes)? != Walkable::Never)
 }
 
+/// Calculates the cost of moving from one tile to the next. Moving between tiles we spend half of
+/// the time on each of them, and we may have to wait before entering the next one.
+fn step_cost(distance_cost: f32, from: &WalkCost, to: &WalkCost) -> i32 {
+    let multiplier = (from.multiplier + to.multiplier) * 0.5;
+    ((distance_cost * multiplier + to.delay) * COST_MULTIPLIER) as i32
+}
+
 fn heuristic(node: Point2<i32>, start: Point2<i32>, costs: &Costs) -> i32 {
-    let cost_straight = (costs.straight * COST_MULTIPLIER) as i32;
-    let cost_diagonal = (costs.diagonal * COST_MULTIPLIER) as i32;
+    let cost_straight = (costs.straight * costs.lowest_multiplier * COST_MULTIPLIER) as i32;
+    let cost_diagonal = (costs.diagonal * costs.lowest_multiplier * COST_MULTIPLIER) as i32;
 
     let dx = (node.x - start.x).abs();
     let dy = (node.y - start.y).abs();
     cost_straight*(dx + dy) + (cost_diagonal - 2*cost_straight) * dx.min(dy)
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        pathfinding::{Pathfinder},
+        test_utils::{self, WALL},
+    };
+
+    #[test]
+    fn records_failed_searches_only_while_debugging() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 5));
+        for y in 1..4 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(3, y), WALL);
+        }
+        let (start, goal) = (Point2::new(1, 2), Point2::new(5, 2));
+        let find_path = |pathfinder: &Pathfinder|
+            pathfinder.find_path(start, goal, true, 1.0, &ship.tiles, &object_classes);
+
+        // Not built yet, so these always do a full search
+        let mut pathfinder = Pathfinder::new();
+        assert!(find_path(&pathfinder).is_none());
+        assert!(pathfinder.last_failed_search().is_none());
+
+        pathfinder.set_debug(true);
+        assert!(find_path(&pathfinder).is_none());
+        assert!(!pathfinder.last_failed_search().as_ref().unwrap().explored.is_empty());
+
+        pathfinder.handle_tiles_changed(&ship.tiles, &object_classes).unwrap();
+        assert!(pathfinder.last_failed_search().is_none());
+    }
+
+    #[test]
+    fn records_failed_searches_once_built() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 5));
+        let (start, goal) = (Point2::new(1, 2), Point2::new(5, 2));
+
+        let mut pathfinder = Pathfinder::new();
+        pathfinder.set_debug(true);
+        pathfinder.handle_tiles_changed(&ship.tiles, &object_classes).unwrap();
+        assert!(pathfinder.find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .is_some());
+
+        // Cut the goal off, the regions already know there's no path but it still gets recorded
+        for y in 1..4 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(3, y), WALL);
+        }
+        pathfinder.handle_tiles_changed(&ship.tiles, &object_classes).unwrap();
+        assert!(!pathfinder.regions().can_reach(start, goal, true));
+
+        assert!(pathfinder.find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .is_none());
+        assert!(!pathfinder.last_failed_search().as_ref().unwrap().explored.is_empty());
+
+        *pathfinder.last_failed_search.borrow_mut() = None;
+        assert!(pathfinder.find_path_shared(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .is_none());
+        assert!(!pathfinder.last_failed_search().as_ref().unwrap().explored.is_empty());
+    }
+}
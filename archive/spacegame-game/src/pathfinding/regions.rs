@@ -0,0 +1,139 @@
+use {
+    cgmath::{Point2},
+
+    lagato::{grid::{Tiles}},
+
+    flood::{Labels, straight_neighbors},
+    object_class::{ObjectClasses},
+    pathfinding::{is_walkable},
+    state::ship::{Tile},
+    Error,
+};
+
+/// Connected areas of walkable tiles, with doors counting as passable. If two tiles aren't in the
+/// same region, there's no path between them.
+pub struct Regions {
+    labels: Labels,
+}
+
+impl Regions {
+    pub fn new() -> Self {
+        Regions {
+            labels: Labels::new(),
+        }
+    }
+
+    /// Labels all tiles with the region they're in. Diagonal movement can't go through hard
+    /// corners, so it never connects anything straight movement doesn't already connect.
+    pub fn rebuild(
+        &mut self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        self.labels = Labels::build(tiles, |tile| is_walkable(Ok(tile), object_classes))?;
+
+        Ok(())
+    }
+
+    pub fn region_at(&self, position: Point2<i32>) -> Option<RegionId> {
+        self.labels.label_at(position).map(RegionId)
+    }
+
+    /// Checks if a path could be found from the start to the goal, following the same rules as
+    /// `find_path`, without doing any pathfinding.
+    pub fn can_reach(&self, start: Point2<i32>, goal: Point2<i32>, goal_inclusive: bool) -> bool {
+        // Moving right next to the goal doesn't need any tiles in between
+        let offset = goal - start;
+        if offset.x.abs() <= 1 && offset.y.abs() <= 1 &&
+           (!goal_inclusive || offset.x == 0 || offset.y == 0) {
+            return true
+        }
+
+        // Pathfinding allows moving off a blocked start tile
+        let start_regions = if let Some(region) = self.region_at(start) {
+            vec!(region)
+        } else {
+            self.regions_around(&straight_neighbors(start))
+        };
+
+        // If we only need to get next to the goal, we can also come from a diagonal
+        let goal_regions = if goal_inclusive {
+            if let Some(region) = self.region_at(goal) {
+                vec!(region)
+            } else {
+                self.regions_around(&straight_neighbors(goal))
+            }
+        } else {
+            let mut neighbors = straight_neighbors(goal).to_vec();
+            neighbors.extend_from_slice(&diagonal_neighbors(goal));
+            neighbors.push(goal);
+            self.regions_around(&neighbors)
+        };
+
+        start_regions.iter().any(|region| goal_regions.contains(region))
+    }
+
+    fn regions_around(&self, positions: &[Point2<i32>]) -> Vec<RegionId> {
+        positions.iter()
+            .filter_map(|position| self.region_at(*position))
+            .collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RegionId(pub u32);
+
+fn diagonal_neighbors(position: Point2<i32>) -> [Point2<i32>; 4] {
+    [
+        Point2::new(position.x + 1, position.y + 1),
+        Point2::new(position.x - 1, position.y + 1),
+        Point2::new(position.x + 1, position.y - 1),
+        Point2::new(position.x - 1, position.y - 1),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        pathfinding::{Regions},
+        test_utils::{self, WALL},
+    };
+
+    #[test]
+    fn can_leave_a_blocked_start() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 5));
+        let start = Point2::new(2, 2);
+        test_utils::place(&mut ship, &object_classes, start, WALL);
+
+        let mut regions = Regions::new();
+        regions.rebuild(&ship.tiles, &object_classes).unwrap();
+
+        assert_eq!(regions.region_at(start), None);
+        assert!(regions.can_reach(start, Point2::new(5, 3), true));
+    }
+
+    #[test]
+    fn non_inclusive_goal_can_be_reached_diagonally() {
+        // The goal is walled in on its straight sides, only its diagonals are open
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        let goal = Point2::new(3, 3);
+        for position in &[goal, Point2::new(2, 3), Point2::new(4, 3), Point2::new(3, 2)] {
+            test_utils::place(&mut ship, &object_classes, *position, WALL);
+        }
+        for x in 1..6 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(x, 4), WALL);
+        }
+
+        let mut regions = Regions::new();
+        regions.rebuild(&ship.tiles, &object_classes).unwrap();
+
+        let start = Point2::new(1, 1);
+        assert!(regions.can_reach(start, goal, false));
+        assert!(!regions.can_reach(start, goal, true));
+
+        // Nothing on the other side of the wall is connected
+        assert!(!regions.can_reach(start, Point2::new(3, 5), false));
+    }
+}
@@ -0,0 +1,151 @@
+use {
+    std::collections::{VecDeque},
+
+    cgmath::{Point2},
+    metrohash::{MetroHashMap},
+
+    lagato::{grid::{Tiles}},
+
+    object_class::{ObjectClasses},
+    pathfinding::{Pathfinder},
+    state::ship::{Tile},
+};
+
+/// How many paths get found every update, to spread the cost of long searches over multiple
+/// updates. This is a fixed amount rather than a time budget so replaying the same updates always
+/// gives the same results.
+const SEARCHES_PER_UPDATE: usize = 4;
+
+/// Paths waiting to be found, solved a few at a time in the order they were requested.
+#[derive(Deserialize, Serialize)]
+pub struct PathRequests {
+    pending: VecDeque<(PathRequestId, PathRequest)>,
+    results: MetroHashMap<PathRequestId, Option<Vec<Point2<i32>>>>,
+    next_id: u32,
+}
+
+impl PathRequests {
+    pub fn new() -> Self {
+        PathRequests {
+            pending: VecDeque::new(),
+            results: MetroHashMap::default(),
+            next_id: 0,
+        }
+    }
+
+    pub fn request(&mut self, request: PathRequest) -> PathRequestId {
+        let id = PathRequestId(self.next_id);
+        self.next_id += 1;
+        self.pending.push_back((id, request));
+
+        id
+    }
+
+    pub fn is_pending(&self, id: PathRequestId) -> bool {
+        self.pending.iter().any(|&(pending_id, _)| pending_id == id)
+    }
+
+    /// Takes the result of a finished request. Returns None if the request isn't finished or
+    /// doesn't exist, Some(None) if no path could be found.
+    pub fn take(&mut self, id: PathRequestId) -> Option<Option<Vec<Point2<i32>>>> {
+        self.results.remove(&id)
+    }
+
+    /// Cancels a request, and throws away its result if it was already finished.
+    pub fn cancel(&mut self, id: PathRequestId) {
+        self.pending.retain(|&(pending_id, _)| pending_id != id);
+        self.results.remove(&id);
+    }
+
+    pub fn update(
+        &mut self, pathfinder: &Pathfinder, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) {
+        for _ in 0..SEARCHES_PER_UPDATE {
+            let (id, request) = if let Some(pending) = self.pending.pop_front() {
+                pending
+            } else {
+                break
+            };
+
+            // If other units are going to the same place, they can share a flow field instead of
+            // all searching on their own
+            let shared = pathfinder.has_flow_field(
+                request.goal, request.goal_inclusive, request.seconds_per_unit,
+            ) || self.pending.iter().any(|&(_, ref other)| request.shares_goal(other));
+
+            let path = if shared {
+                pathfinder.find_path_shared(
+                    request.start, request.goal, request.goal_inclusive, request.seconds_per_unit,
+                    tiles, object_classes,
+                )
+            } else {
+                pathfinder.find_path(
+                    request.start, request.goal, request.goal_inclusive, request.seconds_per_unit,
+                    tiles, object_classes,
+                )
+            };
+            self.results.insert(id, path);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, Deserialize, Serialize)]
+pub struct PathRequestId(pub u32);
+
+#[derive(Deserialize, Serialize)]
+pub struct PathRequest {
+    pub start: Point2<i32>,
+    pub goal: Point2<i32>,
+    pub goal_inclusive: bool,
+    pub seconds_per_unit: f32,
+}
+
+impl PathRequest {
+    /// Checks if a path for the other request would come out of the same flow field.
+    pub fn shares_goal(&self, other: &PathRequest) -> bool {
+        self.goal == other.goal && self.goal_inclusive == other.goal_inclusive &&
+            self.seconds_per_unit == other.seconds_per_unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        pathfinding::{Pathfinder, PathRequests, PathRequest},
+        test_utils,
+    };
+
+    fn request(start: Point2<i32>, goal: Point2<i32>) -> PathRequest {
+        PathRequest {
+            start,
+            goal,
+            goal_inclusive: true,
+            seconds_per_unit: 1.0,
+        }
+    }
+
+    #[test]
+    fn shared_goal_uses_one_flow_field() {
+        let object_classes = test_utils::object_classes();
+        let ship = test_utils::hull_ship(Vector2::new(12, 12));
+        let mut pathfinder = Pathfinder::new();
+        pathfinder.handle_tiles_changed(&ship.tiles, &object_classes).unwrap();
+
+        let goal = Point2::new(10, 10);
+        let mut requests = PathRequests::new();
+        let first = requests.request(request(Point2::new(1, 1), goal));
+        let second = requests.request(request(Point2::new(1, 10), goal));
+        let alone = requests.request(request(Point2::new(1, 1), Point2::new(10, 1)));
+        requests.update(&pathfinder, &ship.tiles, &object_classes);
+
+        assert_eq!(requests.take(first).unwrap().unwrap()[0], goal);
+        assert_eq!(requests.take(second).unwrap().unwrap()[0], goal);
+        assert!(requests.take(alone).unwrap().is_some());
+
+        // Only the goal both of them went to got a field
+        assert_eq!(pathfinder.flow_fields.borrow().len(), 1);
+        assert!(pathfinder.has_flow_field(goal, true, 1.0));
+    }
+}
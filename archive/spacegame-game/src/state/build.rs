@@ -19,6 +19,7 @@ pub enum BuildDrag {
 pub enum BuildChoice {
     None,
     Floor,
+    Hull,
     Object(ObjectClassId),
     Destroy,
     DestroyAll,
@@ -1,17 +1,22 @@
-use cgmath::{Vector2, Point2, Point3, Matrix4, Ortho, SquareMatrix, Transform};
+use cgmath::{Vector2, Point2, Point3, Matrix4, Ortho, SquareMatrix, Transform, EuclideanSpace};
+
+/// The closest the camera can zoom out, in pixels per tile.
+const MIN_PIXELS_PER_TILE: f32 = 8.0;
+/// The furthest the camera can zoom in, in pixels per tile.
+const MAX_PIXELS_PER_TILE: f32 = 256.0;
 
 #[derive(Deserialize, Serialize)]
 pub struct Camera {
     position: Point2<f32>,
-    pixels_per_tile: i32,
+    pixels_per_tile: f32,
     half_screen_size: Vector2<i32>,
 }
 
 impl Camera {
-    pub fn new(pixels_per_tile: i32, screen_size: Vector2<i32>) -> Self {
+    pub fn new(pixels_per_tile: f32, screen_size: Vector2<i32>) -> Self {
         Camera {
             position: Point2::new(0.0, 0.0),
-            pixels_per_tile,
+            pixels_per_tile: clamp_pixels_per_tile(pixels_per_tile),
             half_screen_size: screen_size/2,
         }
     }
@@ -24,10 +29,41 @@ impl Camera {
         self.position = position;
     }
 
-    pub fn pixels_per_tile(&self) -> i32 {
+    pub fn pixels_per_tile(&self) -> f32 {
         self.pixels_per_tile
     }
 
+    /// Sets the zoom level, limited between the minimum and maximum zoom.
+    pub fn set_pixels_per_tile(&mut self, pixels_per_tile: f32) {
+        self.pixels_per_tile = clamp_pixels_per_tile(pixels_per_tile);
+    }
+
+    /// Zooms by a factor, keeping the world position under the given screen position in place.
+    pub fn zoom_around(&mut self, factor: f32, pixels: Point2<i32>) {
+        let before = self.screen_to_world(pixels);
+        let pixels_per_tile = self.pixels_per_tile * factor;
+        self.set_pixels_per_tile(pixels_per_tile);
+        let after = self.screen_to_world(pixels);
+
+        self.position += before - after;
+    }
+
+    /// Zooms by a factor around the center of the screen.
+    pub fn zoom(&mut self, factor: f32) {
+        let center = Point2::from_vec(self.half_screen_size);
+        self.zoom_around(factor, center);
+    }
+
+    /// Keeps the center of the camera inside an area, so it can't be moved away into nothing.
+    pub fn clamp_to(&mut self, start: Point2<f32>, end: Point2<f32>) {
+        self.position.x = self.position.x.max(start.x).min(end.x);
+        self.position.y = self.position.y.max(start.y).min(end.y);
+    }
+
+    pub fn screen_size(&self) -> Vector2<i32> {
+        self.half_screen_size * 2
+    }
+
     pub fn set_screen_size(&mut self, screen_size: Vector2<i32>) {
         self.half_screen_size = screen_size/2;
     }
@@ -70,8 +106,12 @@ impl Camera {
 
     fn half_world_size(&self) -> Vector2<f32> {
         Vector2::new(
-            self.half_screen_size.x as f32 / self.pixels_per_tile as f32,
-            self.half_screen_size.y as f32 / self.pixels_per_tile as f32,
+            self.half_screen_size.x as f32 / self.pixels_per_tile,
+            self.half_screen_size.y as f32 / self.pixels_per_tile,
         )
     }
 }
+
+fn clamp_pixels_per_tile(pixels_per_tile: f32) -> f32 {
+    pixels_per_tile.max(MIN_PIXELS_PER_TILE).min(MAX_PIXELS_PER_TILE)
+}
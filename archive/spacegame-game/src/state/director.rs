@@ -0,0 +1,324 @@
+use {
+    cgmath::{Point2},
+    slog::{Logger},
+
+    object_class::{ObjectClasses},
+    pathfinding::{Walkable},
+    state::{
+        Rng, Notifications,
+        ship::{Ship, TileLayer, Unit, CrewMember, Role, MAX_UNIT_HEALTH},
+    },
+    Error,
+};
+
+/// Seconds before the first event, so a new ship gets some time to settle in.
+const GRACE_PERIOD: f32 = 120.0;
+
+const FIRST_NAMES: [&str; 8] = [
+    "Iris", "Jonah", "Mei", "Oskar", "Priya", "Ruben", "Saoirse", "Yusuf",
+];
+const LAST_NAMES: [&str; 8] = [
+    "Adeyemi", "Brandt", "Castillo", "Dahl", "Ito", "Kovac", "Lund", "Osei",
+];
+
+/// Decides when something happens to the ship from the outside, and what.
+#[derive(Deserialize, Serialize)]
+pub struct Director {
+    pub frequency: EventFrequency,
+    pub difficulty: Difficulty,
+    time_until_event: f32,
+}
+
+impl Director {
+    pub fn new() -> Self {
+        Director {
+            frequency: EventFrequency::Normal,
+            difficulty: Difficulty::Normal,
+            time_until_event: GRACE_PERIOD,
+        }
+    }
+
+    pub fn update(
+        &mut self, log: &Logger, object_classes: &ObjectClasses,
+        ship: &mut Ship, rng: &mut Rng, notifications: &mut Notifications, delta: f32,
+    ) -> Result<(), Error> {
+        let mean_seconds = if let Some(seconds) = self.frequency.mean_seconds() {
+            seconds
+        } else {
+            return Ok(())
+        };
+
+        self.time_until_event -= delta;
+        if self.time_until_event > 0.0 {
+            return Ok(())
+        }
+
+        // Vary the time between events, so they don't feel like they're on a schedule
+        self.time_until_event = mean_seconds * (0.5 + rng.next_f32());
+
+        let event = self.pick_event(rng);
+        self.trigger(log, object_classes, ship, rng, notifications, event)
+    }
+
+    /// Picks an event, harder difficulties make damaging events more likely.
+    fn pick_event(&self, rng: &mut Rng) -> Event {
+        let scale = self.difficulty.scale();
+        let weights: Vec<_> = Event::all().iter()
+            .map(|event| if event.is_harmful() { scale } else { 1.0 / scale })
+            .collect();
+
+        let mut roll = rng.next_f32() * weights.iter().sum::<f32>();
+        for (event, weight) in Event::all().iter().zip(weights) {
+            if roll < weight {
+                return *event
+            }
+            roll -= weight;
+        }
+
+        *Event::all().last().unwrap()
+    }
+
+    /// Makes the event happen to the ship and tells the player about it.
+    pub fn trigger(
+        &self, log: &Logger, object_classes: &ObjectClasses,
+        ship: &mut Ship, rng: &mut Rng, notifications: &mut Notifications, event: Event,
+    ) -> Result<(), Error> {
+        let scale = self.difficulty.scale();
+
+        let text = match event {
+            Event::MeteorShower => {
+                let targets = ship_tiles(ship);
+                let impacts = (rng.range(1, 4) as f32 * scale).ceil() as i32;
+                for _ in 0..impacts {
+                    if let Some(center) = rng.choose(&targets).cloned() {
+                        let radius = rng.range(0, 2);
+                        ship.meteor_impact(log, object_classes, center, radius, 120.0 * scale)?;
+                    }
+                }
+                format!("Meteor shower, {} impacts on the hull", impacts)
+            },
+            Event::SystemFailure => {
+                let targets = open_floor(ship, object_classes)?;
+                if let Some(position) = rng.choose(&targets).cloned() {
+                    ship.fires.ignite(position, 0.3 * scale);
+                    format!(
+                        "System failure, a fire broke out at ({}, {})", position.x, position.y,
+                    )
+                } else {
+                    "System failure, but nothing caught fire".into()
+                }
+            },
+            Event::DistressCall => {
+                if let Some(name) = spawn_crew(log, object_classes, ship, rng, 0.4)? {
+                    format!("Answered a distress call, {} came aboard injured", name)
+                } else {
+                    "Picked up a distress call, but there's no room aboard for survivors".into()
+                }
+            },
+            Event::CrewArrival => {
+                if let Some(name) = spawn_crew(log, object_classes, ship, rng, 1.0)? {
+                    format!("{} joined the crew", name)
+                } else {
+                    "A shuttle arrived, but there's no room aboard for new crew".into()
+                }
+            },
+        };
+
+        info!(log, "Event: {}", text);
+        notifications.push(text);
+
+        Ok(())
+    }
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Director::new()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Event {
+    MeteorShower,
+    SystemFailure,
+    DistressCall,
+    CrewArrival,
+}
+
+impl Event {
+    pub fn all() -> &'static [Event] {
+        &[Event::MeteorShower, Event::SystemFailure, Event::DistressCall, Event::CrewArrival]
+    }
+
+    pub fn is_harmful(self) -> bool {
+        match self {
+            Event::MeteorShower | Event::SystemFailure => true,
+            Event::DistressCall | Event::CrewArrival => false,
+        }
+    }
+}
+
+/// How often the director triggers events.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EventFrequency {
+    Off,
+    Rare,
+    Normal,
+    Frequent,
+}
+
+impl EventFrequency {
+    pub fn all() -> &'static [EventFrequency] {
+        &[
+            EventFrequency::Off, EventFrequency::Rare,
+            EventFrequency::Normal, EventFrequency::Frequent,
+        ]
+    }
+
+    /// Goes to the next frequency, wrapping around after the last one.
+    pub fn cycle(self) -> Self {
+        let all = EventFrequency::all();
+        let index = all.iter().position(|f| *f == self).unwrap();
+        all[(index + 1) % all.len()]
+    }
+
+    /// Average seconds between events, or None if there aren't any.
+    pub fn mean_seconds(self) -> Option<f32> {
+        match self {
+            EventFrequency::Off => None,
+            EventFrequency::Rare => Some(600.0),
+            EventFrequency::Normal => Some(300.0),
+            EventFrequency::Frequent => Some(120.0),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            EventFrequency::Off => "Off",
+            EventFrequency::Rare => "Rare",
+            EventFrequency::Normal => "Normal",
+            EventFrequency::Frequent => "Frequent",
+        }
+    }
+}
+
+/// How hard events hit the ship.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn all() -> &'static [Difficulty] {
+        &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+    }
+
+    /// Goes to the next difficulty, wrapping around after the last one.
+    pub fn cycle(self) -> Self {
+        let all = Difficulty::all();
+        let index = all.iter().position(|d| *d == self).unwrap();
+        all[(index + 1) % all.len()]
+    }
+
+    /// Multiplier for how much damage events do.
+    pub fn scale(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 2.0,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+/// All tiles that are part of the ship, in a fixed order.
+fn ship_tiles(ship: &Ship) -> Vec<Point2<i32>> {
+    ship.tiles.iter_pos()
+        .filter(|position| ship.tiles.get(*position).unwrap().layer != TileLayer::Space)
+        .collect()
+}
+
+/// All tiles inside the hull that can be stood on, in a fixed order.
+fn open_floor(ship: &Ship, object_classes: &ObjectClasses) -> Result<Vec<Point2<i32>>, Error> {
+    let mut positions = Vec::new();
+    for position in ship.tiles.iter_pos() {
+        if ship.enclosure().is_enclosed(position) &&
+           ship.tiles.get(position)?.walkable(object_classes)? == Walkable::Always {
+            positions.push(position);
+        }
+    }
+    Ok(positions)
+}
+
+/// Adds a crew member with a random name and role somewhere inside the ship, returns their name
+/// if there was anywhere to put them.
+fn spawn_crew(
+    log: &Logger, object_classes: &ObjectClasses, ship: &mut Ship, rng: &mut Rng, health: f32,
+) -> Result<Option<String>, Error> {
+    let positions = open_floor(ship, object_classes)?;
+    let position = if let Some(position) = rng.choose(&positions) {
+        Point2::new(position.x as f32 + 0.5, position.y as f32 + 0.5)
+    } else {
+        return Ok(None)
+    };
+
+    let name = format!(
+        "{} {}", rng.choose(&FIRST_NAMES).unwrap(), rng.choose(&LAST_NAMES).unwrap(),
+    );
+    let role = *rng.choose(Role::all()).unwrap();
+
+    let mut unit = Unit::new(position, CrewMember::new(name.clone(), role));
+    unit.damage(log, MAX_UNIT_HEALTH * (1.0 - health));
+    ship.add_unit(unit);
+
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Vector2},
+
+        state::{Director, EventFrequency, Notifications, Rng},
+        test_utils::{self, DELTA},
+    };
+
+    /// Runs a director on a fresh ship for a while, and gets what it told the player.
+    fn events(seed: u64) -> Vec<String> {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(12, 12));
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        let mut director = Director::new();
+        director.frequency = EventFrequency::Frequent;
+        let mut rng = Rng::new(seed);
+
+        let mut texts = Vec::new();
+        for _ in 0..20_000 {
+            let mut notifications = Notifications::new();
+            director.update(
+                &log, &object_classes, &mut ship, &mut rng, &mut notifications, DELTA,
+            ).unwrap();
+            texts.extend(notifications.entries().iter().map(|entry| entry.text.clone()));
+        }
+        texts
+    }
+
+    #[test]
+    fn same_seed_gives_same_events() {
+        let first = events(1234);
+        assert!(first.len() > 5);
+        assert_eq!(first, events(1234));
+        assert_ne!(first, events(4321));
+    }
+}
@@ -1,32 +1,63 @@
 pub mod ship;
 mod build;
 mod camera;
+mod director;
+mod notifications;
+mod rng;
+mod selection;
+mod speed;
 
 pub use self::{
     build::{BuildState, BuildDrag, BuildChoice, normalize_area},
     camera::{Camera},
+    director::{Director, Event, EventFrequency, Difficulty},
+    notifications::{Notifications, Notification},
+    rng::{Rng},
+    selection::{SelectionState, SelectionDrag},
+    speed::{SpeedState, Speed},
 };
 
 use {
+    std::time::{SystemTime, UNIX_EPOCH},
+
     cgmath::{Vector2, Point2},
     slog::{Logger},
 
     object_class::{ObjectClasses},
-    state::ship::{Ship},
+    state::ship::{Ship, Overlay},
     Error,
 };
 
 #[derive(Deserialize, Serialize)]
 pub struct GameState {
     pub build_state: BuildState,
+    pub selection: SelectionState,
     pub camera: Camera,
+    pub speed: SpeedState,
+    /// Which per-tile values are shown over the ship, if any.
+    pub overlay: Option<Overlay>,
+    /// If unit paths and failed path searches are shown.
+    pub path_debug: bool,
     pub ship: Ship,
+    /// All randomness in the simulation comes from here, so a seed always plays out the same.
+    pub rng: Rng,
+    pub director: Director,
+    pub notifications: Notifications,
 }
 
 impl GameState {
     pub fn new(log: &Logger) -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs() ^ u64::from(time.subsec_nanos()))
+            .unwrap_or(0);
+        GameState::with_seed(log, seed)
+    }
+
+    pub fn with_seed(log: &Logger, seed: u64) -> Self {
+        info!(log, "Starting game with seed {}", seed);
+
         // Set up the game world camera
-        let mut camera = Camera::new(64, Vector2::new(1280, 720));
+        let mut camera = Camera::new(64.0, Vector2::new(1280, 720));
         camera.set_position(Point2::new(50.0, 50.0));
 
         // Create the starter ship
@@ -37,15 +68,42 @@ impl GameState {
                 drag: BuildDrag::Hovering { position: None, },
                 choice: BuildChoice::None,
             },
+            selection: SelectionState::new(),
             camera,
+            speed: SpeedState::new(),
+            overlay: None,
+            path_debug: false,
             ship,
+            rng: Rng::new(seed),
+            director: Director::new(),
+            notifications: Notifications::new(),
         }
     }
 
     pub fn update(
         &mut self, log: &Logger, object_classes: &ObjectClasses, delta: f32
     ) -> Result<(), Error> {
-        self.ship.update(log, object_classes, delta)?;
+        self.ship.pathfinder_mut().set_debug(self.path_debug);
+
+        // Every tick uses the same delta, however fast the game is going. Positions are remembered
+        // right before each tick so drawing smooths out the latest one, or right away when paused
+        // so units settle where they are.
+        let ticks = self.speed.take_ticks();
+        if ticks == 0 {
+            self.ship.remember_positions();
+        }
+        for _ in 0..ticks {
+            self.ship.remember_positions();
+            self.ship.update(log, object_classes, delta)?;
+            self.director.update(
+                log, object_classes,
+                &mut self.ship, &mut self.rng, &mut self.notifications,
+                delta,
+            )?;
+        }
+
+        // Notifications go away in real time, so they can be read at any speed and while paused
+        self.notifications.update(delta);
 
         Ok(())
     }
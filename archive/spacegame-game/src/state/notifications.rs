@@ -0,0 +1,47 @@
+/// How long a notification stays on screen, in seconds of real time.
+const NOTIFICATION_TIME: f32 = 10.0;
+/// How many notifications are kept at once, older ones get pushed out.
+const MAX_NOTIFICATIONS: usize = 5;
+
+/// Short messages telling the player about things that happened, newest last.
+#[derive(Deserialize, Serialize)]
+pub struct Notifications {
+    entries: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Notifications {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &Vec<Notification> {
+        &self.entries
+    }
+
+    pub fn push(&mut self, text: String) {
+        self.entries.push(Notification {
+            text,
+            time_left: NOTIFICATION_TIME,
+        });
+
+        if self.entries.len() > MAX_NOTIFICATIONS {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        for entry in &mut self.entries {
+            entry.time_left -= delta;
+        }
+        self.entries.retain(|entry| entry.time_left > 0.0);
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Notification {
+    pub text: String,
+    /// How many seconds until the notification goes away.
+    pub time_left: f32,
+}
@@ -0,0 +1,47 @@
+/// A small seeded random number generator, saved with the game so a loaded game keeps rolling
+/// the same numbers it would have. Uses xorshift64*, which is plenty for gameplay.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero, and similar seeds should still give different numbers
+        let state = (seed ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        Rng {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Gets a number from 0.0 up to but not including 1.0.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Gets a number from `start` up to but not including `end`.
+    pub fn range(&mut self, start: i32, end: i32) -> i32 {
+        if end <= start {
+            return start
+        }
+
+        start + (self.next_u64() % (end - start) as u64) as i32
+    }
+
+    /// Picks a random entry from the slice, if it has any.
+    pub fn choose<'a, T>(&mut self, entries: &'a [T]) -> Option<&'a T> {
+        if entries.is_empty() {
+            None
+        } else {
+            let index = self.range(0, entries.len() as i32) as usize;
+            Some(&entries[index])
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use {
+    cgmath::{Point2},
+    state::ship::{UnitId},
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct SelectionState {
+    pub selected: Vec<UnitId>,
+    pub drag: SelectionDrag,
+}
+
+impl SelectionState {
+    pub fn new() -> Self {
+        SelectionState {
+            selected: Vec::new(),
+            drag: SelectionDrag::None,
+        }
+    }
+
+    pub fn is_selected(&self, id: UnitId) -> bool {
+        self.selected.contains(&id)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum SelectionDrag {
+    None,
+    /// Dragging a box in world coordinates to select everything inside of it.
+    Dragging { start: Point2<f32>, end: Point2<f32> },
+}
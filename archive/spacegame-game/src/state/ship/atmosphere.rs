@@ -0,0 +1,90 @@
+use {
+    cgmath::{Point2, Vector2},
+
+    lagato::{grid::{Tiles}},
+
+    state::ship::{Tile, Rooms, Enclosure, Fires, fire::{OXYGEN_USE}},
+};
+
+/// How much oxygen a room open to space loses per second.
+const VENT_RATE: f32 = 0.5;
+/// How much oxygen life support adds back to a sealed room per second.
+const REFILL_RATE: f32 = 0.02;
+
+/// How much oxygen there is on each tile, from 0.0 for vacuum to 1.0 for breathable air. Air
+/// mixes freely within a room, so all tiles in a room always have the same amount.
+#[derive(Deserialize, Serialize)]
+pub struct Atmosphere {
+    size: Vector2<i32>,
+    oxygen: Vec<f32>,
+}
+
+impl Atmosphere {
+    pub fn new(size: Vector2<i32>) -> Self {
+        Atmosphere {
+            size,
+            oxygen: vec!(1.0; (size.x * size.y) as usize),
+        }
+    }
+
+    pub fn oxygen(&self, position: Point2<i32>) -> f32 {
+        if position.x < 0 || position.y < 0 ||
+           position.x >= self.size.x || position.y >= self.size.y {
+            return 0.0
+        }
+
+        self.oxygen[self.index(position)]
+    }
+
+    pub fn set_oxygen(&mut self, position: Point2<i32>, oxygen: f32) {
+        if position.x < 0 || position.y < 0 ||
+           position.x >= self.size.x || position.y >= self.size.y {
+            return
+        }
+
+        let index = self.index(position);
+        self.oxygen[index] = oxygen.max(0.0).min(1.0);
+    }
+
+    /// Mixes the air in every room, venting rooms that are open to space, burning air in rooms
+    /// that are on fire and slowly refilling sealed ones.
+    pub fn update(
+        &mut self,
+        tiles: &Tiles<Tile>, rooms: &Rooms, enclosure: &Enclosure, fires: &Fires, delta: f32,
+    ) {
+        // Add up the air and fires in each room, a room vents if any of it is exposed
+        let amount = rooms.amount() as usize;
+        let mut totals = vec!(0.0; amount);
+        let mut counts = vec!(0; amount);
+        let mut vented = vec!(false; amount);
+        let mut burning = vec!(0.0; amount);
+        for position in tiles.iter_pos() {
+            if let Some(room) = rooms.room_at(position) {
+                let room = room.0 as usize;
+                totals[room] += self.oxygen(position);
+                counts[room] += 1;
+                vented[room] |= !enclosure.is_enclosed(position);
+                burning[room] += fires.intensity(position);
+            }
+        }
+
+        for position in tiles.iter_pos() {
+            if let Some(room) = rooms.room_at(position) {
+                let room = room.0 as usize;
+                let average = totals[room] / counts[room] as f32;
+                let change = if vented[room] {
+                    -VENT_RATE
+                } else if burning[room] > 0.0 {
+                    -OXYGEN_USE * burning[room]
+                } else {
+                    REFILL_RATE
+                };
+                self.set_oxygen(position, average + change * delta);
+            }
+        }
+    }
+
+    fn index(&self, position: Point2<i32>) -> usize {
+        (position.x + position.y * self.size.x) as usize
+    }
+}
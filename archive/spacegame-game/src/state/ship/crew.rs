@@ -0,0 +1,143 @@
+/// What a crew member was brought on board to do, decides what they start out being good at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Role {
+    Engineer,
+    Medic,
+    Hauler,
+}
+
+impl Role {
+    pub fn all() -> &'static [Role] {
+        &[Role::Engineer, Role::Medic, Role::Hauler]
+    }
+
+    pub fn starting_skills(self) -> Skills {
+        match self {
+            Role::Engineer => Skills { construction: 8, medicine: 2, hauling: 4, eva: 5 },
+            Role::Medic => Skills { construction: 3, medicine: 8, hauling: 4, eva: 0 },
+            Role::Hauler => Skills { construction: 4, medicine: 2, hauling: 8, eva: 2 },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Skill {
+    Construction,
+    Medicine,
+    Hauling,
+    /// Working in a suit outside the hull.
+    Eva,
+}
+
+/// Skill levels of a crew member, from 0 to `MAX_SKILL_LEVEL`.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Skills {
+    pub construction: u32,
+    pub medicine: u32,
+    pub hauling: u32,
+    pub eva: u32,
+}
+
+pub const MAX_SKILL_LEVEL: u32 = 10;
+
+impl Skills {
+    pub fn level(&self, skill: Skill) -> u32 {
+        match skill {
+            Skill::Construction => self.construction,
+            Skill::Medicine => self.medicine,
+            Skill::Hauling => self.hauling,
+            Skill::Eva => self.eva,
+        }
+    }
+
+    /// How fast work using this skill gets done, 1.0 at level 5, half as fast at level 0 and
+    /// twice as fast at the highest level.
+    pub fn work_speed(&self, skill: Skill) -> f32 {
+        let level = self.level(skill).min(MAX_SKILL_LEVEL) as f32;
+        if level < 5.0 {
+            0.5 + level * 0.1
+        } else {
+            1.0 + (level - 5.0) * 0.2
+        }
+    }
+}
+
+/// Kinds of work crew members can be allowed or disallowed to take on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Job {
+    Build,
+    Repair,
+}
+
+impl Job {
+    pub fn skill(self) -> Skill {
+        match self {
+            Job::Build => Skill::Construction,
+            Job::Repair => Skill::Construction,
+        }
+    }
+
+    pub fn all() -> &'static [Job] {
+        &[Job::Build, Job::Repair]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Job::Build => "Build",
+            Job::Repair => "Repair",
+        }
+    }
+}
+
+/// Who a unit is and what they can do.
+#[derive(Deserialize, Serialize)]
+pub struct CrewMember {
+    pub name: String,
+    pub role: Role,
+    pub skills: Skills,
+    enabled_jobs: Vec<Job>,
+}
+
+impl CrewMember {
+    pub fn new(name: String, role: Role) -> Self {
+        CrewMember {
+            name,
+            role,
+            skills: role.starting_skills(),
+            enabled_jobs: Job::all().to_vec(),
+        }
+    }
+
+    pub fn is_job_enabled(&self, job: Job) -> bool {
+        self.enabled_jobs.contains(&job)
+    }
+
+    pub fn set_job_enabled(&mut self, job: Job, enabled: bool) {
+        self.enabled_jobs.retain(|enabled_job| *enabled_job != job);
+        if enabled {
+            self.enabled_jobs.push(job);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        state::ship::{Skill, Skills},
+    };
+
+    #[test]
+    fn work_speed_scales_with_skill() {
+        let speed = |construction| Skills { construction, medicine: 0, hauling: 0, eva: 0 }
+            .work_speed(Skill::Construction);
+
+        assert_eq!(speed(0), 0.5);
+        assert_eq!(speed(5), 1.0);
+        assert_eq!(speed(10), 2.0);
+        assert!(speed(3) < speed(4));
+        assert!(speed(7) < speed(8));
+
+        // Levels past the highest don't make work any faster
+        assert_eq!(speed(20), 2.0);
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    cgmath::{Point2},
+    slog::{Logger},
+
+    object_class::{ObjectClasses},
+    state::ship::{Ship, Task, TileLayer},
+    Error,
+};
+
+/// What caused damage to the ship.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DamageSource {
+    Meteor,
+    Fire,
+}
+
+impl DamageSource {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DamageSource::Meteor => "meteor",
+            DamageSource::Fire => "fire",
+        }
+    }
+}
+
+impl Ship {
+    /// Damages the object on a tile, or the tile itself if there's no object. Objects that run out
+    /// of health are destroyed and tiles are breached, the tile gets a repair task if it needs one.
+    pub fn damage_tile(
+        &mut self, log: &Logger, object_classes: &ObjectClasses,
+        position: Point2<i32>, amount: f32, source: DamageSource,
+    ) -> Result<(), Error> {
+        let mut changed = false;
+        let destroyed;
+        let needs_repair = {
+            let tile = self.tiles.get_mut(position)?;
+            if tile.layer == TileLayer::Space || amount <= 0.0 {
+                return Ok(())
+            }
+
+            destroyed = if let Some(ref mut object) = tile.object {
+                object.damage += amount;
+                object.damage >= object_classes.get(object.class)?.max_health
+            } else {
+                let was_breached = tile.is_breached();
+                tile.damage = (tile.damage + amount).min(tile.layer.max_health());
+                if !was_breached && tile.is_breached() {
+                    warn!(
+                        log, "{} at ({}, {}) breached by {}",
+                        tile.layer.describe(), position.x, position.y, source.describe()
+                    );
+                    changed = true;
+                }
+                false
+            };
+
+            if destroyed {
+                info!(
+                    log, "Object at ({}, {}) destroyed by {}",
+                    position.x, position.y, source.describe()
+                );
+                tile.object = None;
+                changed = true;
+            }
+
+            tile.damage > 0.0 ||
+                tile.object.as_ref().map(|object| object.damage > 0.0).unwrap_or(false)
+        };
+
+        if changed {
+            self.tiles.changed.raise();
+        }
+        if destroyed {
+            self.forget_behavior(position);
+        }
+
+        // Only one task can be on a tile, anything already there has to be done first
+        if needs_repair && self.task_queue.get_at(position).is_none() {
+            self.task_queue.queue(Task::repair(position))?;
+        }
+
+        Ok(())
+    }
+
+    /// Damages all tiles in a circle around where a meteor hit, less the further they are from
+    /// the center.
+    pub fn meteor_impact(
+        &mut self, log: &Logger, object_classes: &ObjectClasses,
+        center: Point2<i32>, radius: i32, damage: f32,
+    ) -> Result<(), Error> {
+        info!(log, "Meteor impact at ({}, {})", center.x, center.y);
+
+        for y in (center.y - radius)..(center.y + radius + 1) {
+            for x in (center.x - radius)..(center.x + radius + 1) {
+                let position = Point2::new(x, y);
+                let (dx, dy) = ((x - center.x) as f32, (y - center.y) as f32);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if !self.tiles.is_in_bounds(position) || distance > radius as f32 + 0.5 {
+                    continue
+                }
+
+                let falloff = 1.0 - distance / (radius as f32 + 1.0);
+                self.damage_tile(
+                    log, object_classes, position, damage * falloff, DamageSource::Meteor,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        state::ship::{DamageSource, TaskKind, Skill, MIN_EVA_LEVEL},
+        test_utils::{self},
+    };
+
+    #[test]
+    fn breach_vents_until_repaired() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 5));
+        let unit = test_utils::add_unit(&mut ship, Point2::new(3, 2));
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        // Breaches are fixed from the inside, so the unit doesn't need to be able to go outside
+        assert!(ship.unit(unit).unwrap().crew().skills.level(Skill::Eva) < MIN_EVA_LEVEL);
+
+        let log = test_utils::logger();
+        let breach = Point2::new(0, 2);
+        ship.damage_tile(&log, &object_classes, breach, 1000.0, DamageSource::Meteor).unwrap();
+        assert!(ship.tiles.get(breach).unwrap().is_breached());
+
+        // The room is open to space now, so it loses its air
+        let task_id = ship.task_queue.get_at(breach).unwrap();
+        assert_eq!(ship.task_queue.get(task_id).unwrap().kind, TaskKind::Repair);
+        test_utils::run(&mut ship, &object_classes, 5);
+        assert!(!ship.enclosure().is_enclosed(Point2::new(3, 2)));
+        assert!(ship.atmosphere.oxygen(Point2::new(3, 2)) < 1.0);
+
+        // The unit walks over and patches the hull, sealing the room again
+        let mut ticks = 0;
+        while ship.task_queue.get(task_id).is_some() {
+            assert!(ticks < 500, "Repair didn't finish");
+            test_utils::run(&mut ship, &object_classes, 1);
+            ticks += 1;
+        }
+        assert!(!ship.tiles.get(breach).unwrap().is_breached());
+
+        test_utils::run(&mut ship, &object_classes, 1);
+        assert!(ship.enclosure().is_enclosed(Point2::new(3, 2)));
+        let oxygen = ship.atmosphere.oxygen(Point2::new(3, 2));
+        test_utils::run(&mut ship, &object_classes, 10);
+        assert!(ship.atmosphere.oxygen(Point2::new(3, 2)) > oxygen);
+    }
+}
@@ -0,0 +1,128 @@
+use {
+    cgmath::{Point2, Vector2},
+
+    lagato::{grid::{Tiles}},
+
+    flood::{flood_fill},
+    object_class::{ObjectClasses},
+    pathfinding::{Walkable},
+    state::ship::{Tile, TileLayer},
+    Error,
+};
+
+/// Which floor tiles are sealed off from space by hull, and so can hold atmosphere. Also keeps
+/// track of which floor is inside the ship at all, even if a breach is letting the air out.
+pub struct Enclosure {
+    size: Vector2<i32>,
+    enclosed: Vec<bool>,
+    inside: Vec<bool>,
+}
+
+impl Enclosure {
+    pub fn new() -> Self {
+        Enclosure {
+            size: Vector2::new(0, 0),
+            enclosed: Vec::new(),
+            inside: Vec::new(),
+        }
+    }
+
+    /// Finds all floor tiles that space can't reach, and those it couldn't reach if all breaches
+    /// were repaired.
+    pub fn rebuild(
+        &mut self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        self.size = tiles.size();
+        let exposed = self.find_exposed(tiles, object_classes, false)?;
+        let exposed_when_repaired = self.find_exposed(tiles, object_classes, true)?;
+
+        self.enclosed = Vec::with_capacity(exposed.len());
+        self.inside = Vec::with_capacity(exposed.len());
+        for position in tiles.iter_pos() {
+            let tile = tiles.get(position)?;
+            let index = self.index(position);
+            let floor = tile.layer == TileLayer::Floor;
+            self.enclosed.push(floor && !tile.is_breached() && !exposed[index]);
+            self.inside.push(floor && !exposed_when_repaired[index]);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_enclosed(&self, position: Point2<i32>) -> bool {
+        self.is_in_bounds(position) && self.enclosed[self.index(position)]
+    }
+
+    /// Checks if the tile can be worked on while standing inside the ship, meaning it or one of
+    /// its neighbors is floor inside the hull. Breaches don't change this, they're fixed from the
+    /// inside.
+    pub fn is_workable_from_inside(&self, position: Point2<i32>) -> bool {
+        for y in position.y-1..position.y+2 {
+            for x in position.x-1..position.x+2 {
+                let neighbor = Point2::new(x, y);
+                if self.is_in_bounds(neighbor) && self.inside[self.index(neighbor)] {
+                    return true
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Floods out from space through everything that isn't sealed, optionally treating breaches
+    /// as if they were repaired.
+    fn find_exposed(
+        &self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses, ignore_breaches: bool,
+    ) -> Result<Vec<bool>, Error> {
+        let mut exposed = vec!(false; (self.size.x * self.size.y) as usize);
+        let is_sealing = |tile: &Tile| -> Result<bool, Error> {
+            Ok((ignore_breaches && tile.is_breached()) || is_sealing(tile, object_classes)?)
+        };
+
+        // Space leaks in from any open or breached tile, and from past the edges of the ship
+        let mut start = Vec::new();
+        for position in tiles.iter_pos() {
+            let on_edge = position.x == 0 || position.y == 0 ||
+                position.x == self.size.x - 1 || position.y == self.size.y - 1;
+            let tile = tiles.get(position)?;
+            let open_to_space = tile.layer == TileLayer::Space ||
+                (!ignore_breaches && tile.is_breached());
+            if open_to_space || (on_edge && !is_sealing(tile)?) {
+                exposed[self.index(position)] = true;
+                start.push(position);
+            }
+        }
+
+        flood_fill(tiles, start, |neighbor, tile| {
+            let index = self.index(neighbor);
+            if exposed[index] || is_sealing(tile)? {
+                return Ok(false)
+            }
+
+            exposed[index] = true;
+            Ok(true)
+        })?;
+
+        Ok(exposed)
+    }
+
+    fn is_in_bounds(&self, position: Point2<i32>) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.size.x && position.y < self.size.y
+    }
+
+    fn index(&self, position: Point2<i32>) -> usize {
+        (position.x + position.y * self.size.x) as usize
+    }
+}
+
+impl Default for Enclosure {
+    fn default() -> Self {
+        Enclosure::new()
+    }
+}
+
+/// Intact hull keeps space out, and so do doors since they work as airlocks.
+fn is_sealing(tile: &Tile, object_classes: &ObjectClasses) -> Result<bool, Error> {
+    Ok((tile.layer == TileLayer::Hull && !tile.is_breached()) ||
+        tile.walkable(object_classes)? == Walkable::Openable)
+}
@@ -0,0 +1,356 @@
+use {
+    cgmath::{Point2, Vector2, MetricSpace},
+    slog::{Logger},
+
+    flood::{straight_neighbors},
+    object_class::{ObjectClasses},
+    pathfinding::{Walkable},
+    state::ship::{Ship, Tile, TileLayer, DamageSource},
+    Error,
+};
+
+/// Oxygen a tile needs for a fire on it to keep burning.
+const MIN_OXYGEN: f32 = 0.2;
+/// How fast fires grow while they have oxygen, and die down without it, per second.
+const GROW_RATE: f32 = 0.1;
+const BURN_OUT_RATE: f32 = 0.5;
+/// How much of the oxygen in its room a fire at full intensity burns per second, a room that's
+/// burning gets no air from life support so a sealed room fire eventually smothers itself.
+pub const OXYGEN_USE: f32 = 0.02;
+/// How much damage a fire at full intensity does per second, to objects and to units on it.
+const OBJECT_DAMAGE: f32 = 2.0;
+const UNIT_DAMAGE: f32 = 10.0;
+/// How much heat a fire at full intensity gives its neighbors per second, they catch fire once
+/// they reach 1.0 heat.
+const SPREAD_RATE: f32 = 0.2;
+/// How much heat tiles lose per second.
+const COOL_RATE: f32 = 0.05;
+/// How intense a fire is when it starts.
+const START_INTENSITY: f32 = 0.2;
+
+/// Fires burning on tiles, from 0.0 for no fire to 1.0 for fully ablaze, and how close tiles
+/// are to catching fire.
+#[derive(Deserialize, Serialize)]
+pub struct Fires {
+    size: Vector2<i32>,
+    intensity: Vec<f32>,
+    heat: Vec<f32>,
+}
+
+impl Fires {
+    pub fn new(size: Vector2<i32>) -> Self {
+        let amount = (size.x * size.y) as usize;
+        Fires {
+            size,
+            intensity: vec!(0.0; amount),
+            heat: vec!(0.0; amount),
+        }
+    }
+
+    pub fn intensity(&self, position: Point2<i32>) -> f32 {
+        if !self.is_in_bounds(position) {
+            return 0.0
+        }
+
+        self.intensity[self.index(position)]
+    }
+
+    pub fn is_burning(&self, position: Point2<i32>) -> bool {
+        self.intensity(position) > 0.0
+    }
+
+    /// Positions of all tiles that are on fire, in a fixed order.
+    pub fn burning(&self) -> Vec<Point2<i32>> {
+        let mut burning = Vec::new();
+        for (index, intensity) in self.intensity.iter().enumerate() {
+            if *intensity > 0.0 {
+                let index = index as i32;
+                burning.push(Point2::new(index % self.size.x, index / self.size.x));
+            }
+        }
+        burning
+    }
+
+    /// Sets a tile on fire, if it isn't burning more intensely already.
+    pub fn ignite(&mut self, position: Point2<i32>, intensity: f32) {
+        if !self.is_in_bounds(position) {
+            return
+        }
+
+        let index = self.index(position);
+        self.intensity[index] = self.intensity[index].max(intensity.min(1.0));
+    }
+
+    pub fn extinguish(&mut self, position: Point2<i32>) {
+        if !self.is_in_bounds(position) {
+            return
+        }
+
+        let index = self.index(position);
+        self.intensity[index] = 0.0;
+        self.heat[index] = 0.0;
+    }
+
+    fn set_intensity(&mut self, position: Point2<i32>, intensity: f32) {
+        let index = self.index(position);
+        self.intensity[index] = intensity.max(0.0).min(1.0);
+    }
+
+    fn add_heat(&mut self, position: Point2<i32>, heat: f32) {
+        if self.is_in_bounds(position) {
+            let index = self.index(position);
+            self.heat[index] += heat;
+        }
+    }
+
+    fn is_in_bounds(&self, position: Point2<i32>) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.size.x && position.y < self.size.y
+    }
+
+    fn index(&self, position: Point2<i32>) -> usize {
+        (position.x + position.y * self.size.x) as usize
+    }
+}
+
+impl Ship {
+    /// Burns, spreads and puts out fires. Everything happens in a fixed order without randomness,
+    /// so the same ship always burns the same way.
+    pub fn update_fires(
+        &mut self, log: &Logger, object_classes: &ObjectClasses, delta: f32,
+    ) -> Result<(), Error> {
+        let burning = self.fires.burning();
+        if burning.is_empty() {
+            return Ok(())
+        }
+
+        // Find everything that fights fires, only needed while something is burning
+        let mut extinguishers = Vec::new();
+        for position in self.tiles.iter_pos() {
+            if let Some(ref object) = self.tiles.get(position)?.object {
+                if let Some(extinguisher) = object_classes.get(object.class)?.extinguisher {
+                    extinguishers.push((position, extinguisher));
+                }
+            }
+        }
+
+        for position in burning {
+            // Closed doors, breaches and walls can't burn, anything still burning there goes out
+            if !can_burn(self.tiles.get(position)?, object_classes)? {
+                self.fires.extinguish(position);
+                continue
+            }
+
+            let intensity = self.fires.intensity(position);
+            let oxygen = self.oxygen_at(position);
+            let suppression: f32 = extinguishers.iter()
+                .filter(|&&(at, extinguisher)| {
+                    to_f32(at).distance(to_f32(position)) <= extinguisher.radius
+                })
+                .map(|&(_, extinguisher)| extinguisher.strength)
+                .sum();
+
+            // Fires need air to grow, venting a room smothers them
+            let growth = if oxygen >= MIN_OXYGEN { GROW_RATE } else { -BURN_OUT_RATE };
+            self.fires.set_intensity(position, intensity + (growth - suppression) * delta);
+
+            for neighbor in &straight_neighbors(position) {
+                self.fires.add_heat(*neighbor, SPREAD_RATE * intensity * delta);
+            }
+
+            // Fires burn what's on the floor, the plating itself holds
+            if self.tiles.get(position)?.object.is_some() {
+                self.damage_tile(
+                    log, object_classes, position, OBJECT_DAMAGE * intensity * delta,
+                    DamageSource::Fire,
+                )?;
+            }
+            if !self.fires.is_burning(position) {
+                info!(log, "Fire at ({}, {}) went out", position.x, position.y);
+            }
+        }
+
+        // Tiles that got hot enough catch fire, the rest slowly cool down
+        for position in self.tiles.iter_pos() {
+            let index = self.fires.index(position);
+            if self.fires.heat[index] >= 1.0 {
+                self.fires.heat[index] = 0.0;
+                if !self.fires.is_burning(position) &&
+                   can_burn(self.tiles.get(position)?, object_classes)? &&
+                   self.oxygen_at(position) >= MIN_OXYGEN {
+                    info!(log, "Fire spread to ({}, {})", position.x, position.y);
+                    self.fires.ignite(position, START_INTENSITY);
+                }
+            } else {
+                self.fires.heat[index] = (self.fires.heat[index] - COOL_RATE * delta).max(0.0);
+            }
+        }
+
+        // Anyone standing in a fire gets hurt
+        let burned: Vec<_> = self.units().iter()
+            .map(|unit| self.fires.intensity(unit.tile_position()))
+            .collect();
+        for (unit, intensity) in self.units_mut().iter_mut().zip(burned) {
+            if intensity > 0.0 {
+                unit.damage(log, UNIT_DAMAGE * intensity * delta);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Air only exists in rooms, so tiles between rooms such as doors get it from their neighbors.
+    fn oxygen_at(&self, position: Point2<i32>) -> f32 {
+        if self.rooms().room_at(position).is_some() {
+            return self.atmosphere.oxygen(position)
+        }
+
+        straight_neighbors(position).iter()
+            .filter(|neighbor| self.rooms().room_at(**neighbor).is_some())
+            .map(|neighbor| self.atmosphere.oxygen(*neighbor))
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Fires burn on open floor, and spread through doors only while they're open.
+fn can_burn(tile: &Tile, object_classes: &ObjectClasses) -> Result<bool, Error> {
+    if tile.layer != TileLayer::Floor || tile.is_breached() {
+        return Ok(false)
+    }
+
+    Ok(match tile.walkable(object_classes)? {
+        Walkable::Always => true,
+        Walkable::Openable => {
+            let object = tile.object.as_ref().unwrap();
+            let class = object_classes.get(object.class)?;
+            class.behavior.as_ref().map(|behavior| behavior.is_open(object)).unwrap_or(false)
+        },
+        Walkable::Never => false,
+    })
+}
+
+fn to_f32(position: Point2<i32>) -> Point2<f32> {
+    Point2::new(position.x as f32, position.y as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        object_class::{ObjectClasses},
+        state::ship::{Ship, MAX_UNIT_HEALTH, fire::{MIN_OXYGEN}},
+        test_utils::{self, WALL, DOOR, EXTINGUISHER},
+    };
+
+    /// Two rooms side by side, split by a wall with a door in the middle at (5, 3).
+    fn two_rooms(door_open: bool) -> (Ship, ObjectClasses) {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(11, 7));
+        for y in 1..6 {
+            let class = if y == 3 { DOOR } else { WALL };
+            test_utils::place(&mut ship, &object_classes, Point2::new(5, y), class);
+        }
+
+        if door_open {
+            // Keep it from closing on its own
+            let door = ship.tiles.get_mut(Point2::new(5, 3)).unwrap().object.as_mut().unwrap();
+            door.values.insert("openness".into(), 1.0);
+            door.values.insert("cooldown".into(), 1000.0);
+        }
+
+        (ship, object_classes)
+    }
+
+    #[test]
+    fn spreads_to_neighbors() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        ship.fires.ignite(Point2::new(3, 3), 1.0);
+
+        test_utils::run(&mut ship, &object_classes, 10);
+        assert!(!ship.fires.is_burning(Point2::new(4, 3)));
+
+        test_utils::run(&mut ship, &object_classes, 90);
+        assert!(ship.fires.is_burning(Point2::new(4, 3)));
+    }
+
+    #[test]
+    fn closed_door_stops_spreading() {
+        let (mut ship, object_classes) = two_rooms(false);
+        ship.fires.ignite(Point2::new(4, 3), 1.0);
+
+        // The fire smothers itself eventually, but never gets past the door before that
+        for _ in 0..300 {
+            test_utils::run(&mut ship, &object_classes, 1);
+            assert!(!ship.fires.is_burning(Point2::new(5, 3)));
+            assert!(!ship.fires.is_burning(Point2::new(6, 3)));
+        }
+    }
+
+    #[test]
+    fn open_door_spreads() {
+        let (mut ship, object_classes) = two_rooms(true);
+        ship.fires.ignite(Point2::new(4, 3), 1.0);
+
+        test_utils::run(&mut ship, &object_classes, 300);
+        assert!(ship.fires.is_burning(Point2::new(6, 3)));
+    }
+
+    #[test]
+    fn vented_room_burns_out() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        ship.fires.ignite(Point2::new(3, 3), 1.0);
+
+        let hull = ship.tiles.get_mut(Point2::new(0, 3)).unwrap();
+        hull.damage = hull.layer.max_health();
+        ship.tiles.changed.raise();
+
+        test_utils::run(&mut ship, &object_classes, 50);
+        assert!(ship.fires.burning().is_empty());
+    }
+
+    #[test]
+    fn sealed_room_fire_smothers_itself() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        ship.fires.ignite(Point2::new(3, 3), 1.0);
+
+        let mut lowest_oxygen: f32 = 1.0;
+        let mut ticks = 0;
+        while !ship.fires.burning().is_empty() {
+            assert!(ticks < 3000, "Fire didn't burn out");
+            test_utils::run(&mut ship, &object_classes, 1);
+            lowest_oxygen = lowest_oxygen.min(ship.atmosphere.oxygen(Point2::new(1, 1)));
+            ticks += 1;
+        }
+        assert!(lowest_oxygen < MIN_OXYGEN);
+
+        // Once it's out life support starts refilling the room
+        let oxygen = ship.atmosphere.oxygen(Point2::new(1, 1));
+        test_utils::run(&mut ship, &object_classes, 10);
+        assert!(ship.atmosphere.oxygen(Point2::new(1, 1)) > oxygen);
+    }
+
+    #[test]
+    fn extinguisher_puts_out_fire() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        test_utils::place(&mut ship, &object_classes, Point2::new(1, 1), EXTINGUISHER);
+        ship.fires.ignite(Point2::new(3, 3), 0.5);
+
+        test_utils::run(&mut ship, &object_classes, 50);
+        assert!(ship.fires.burning().is_empty());
+    }
+
+    #[test]
+    fn hurts_units_standing_in_it() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(7, 7));
+        let unit = test_utils::add_unit(&mut ship, Point2::new(3, 3));
+        ship.fires.ignite(Point2::new(3, 3), 1.0);
+
+        test_utils::run(&mut ship, &object_classes, 10);
+        assert!(ship.unit(unit).unwrap().health() < MAX_UNIT_HEALTH);
+    }
+}
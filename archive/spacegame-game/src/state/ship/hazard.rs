@@ -0,0 +1,131 @@
+use {
+    state::ship::{Ship},
+    Error,
+};
+
+/// How many times as long as a bare floor walking through a fire takes extra, units will go a
+/// long way around to avoid it.
+pub const FIRE_HAZARD: f32 = 4.0;
+/// How many times as long as a bare floor walking through a room open to space takes extra.
+pub const VENTED_HAZARD: f32 = 2.0;
+
+impl Ship {
+    /// Marks tiles that are on fire or in a room open to space as hazards, so units path around
+    /// them when they can. Only raises that the tiles changed if any hazard did, which lets the
+    /// pathfinder update just those tiles.
+    pub fn update_hazards(&mut self) -> Result<(), Error> {
+        // A room vents if any of it is exposed, the same as its air
+        let mut vented = vec!(false; self.rooms().amount() as usize);
+        for position in self.tiles.iter_pos() {
+            if let Some(room) = self.rooms().room_at(position) {
+                vented[room.0 as usize] |= !self.enclosure().is_enclosed(position);
+            }
+        }
+
+        let hazards: Vec<_> = self.tiles.iter_pos()
+            .map(|position| {
+                let mut hazard = 0.0;
+                if self.fires.is_burning(position) {
+                    hazard += FIRE_HAZARD;
+                }
+                if let Some(room) = self.rooms().room_at(position) {
+                    if vented[room.0 as usize] {
+                        hazard += VENTED_HAZARD;
+                    }
+                }
+                (position, hazard)
+            })
+            .collect();
+
+        let mut changed = false;
+        for (position, hazard) in hazards {
+            let tile = self.tiles.get_mut(position)?;
+            if tile.hazard != hazard {
+                tile.hazard = hazard;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.tiles.changed.raise();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        state::ship::{
+            Ship, DamageSource, TileLayer,
+            hazard::{FIRE_HAZARD, VENTED_HAZARD},
+        },
+        test_utils::{self, WALL},
+    };
+
+    fn hazard(ship: &Ship, position: Point2<i32>) -> f32 {
+        ship.tiles.get(position).unwrap().hazard
+    }
+
+    #[test]
+    fn fires_and_vented_rooms_are_hazards() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        // Two rooms split by hull at x 5, walls don't keep space out
+        let mut ship = test_utils::hull_ship(Vector2::new(11, 7));
+        for y in 1..6 {
+            ship.tiles.get_mut(Point2::new(5, y)).unwrap().layer = TileLayer::Hull;
+        }
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        ship.fires.ignite(Point2::new(2, 3), 1.0);
+        ship.damage_tile(&log, &object_classes, Point2::new(10, 3), 1000.0, DamageSource::Meteor)
+            .unwrap();
+        test_utils::run(&mut ship, &object_classes, 1);
+        assert_eq!(hazard(&ship, Point2::new(2, 3)), FIRE_HAZARD);
+        assert_eq!(hazard(&ship, Point2::new(2, 2)), 0.0);
+        assert_eq!(hazard(&ship, Point2::new(7, 3)), VENTED_HAZARD);
+
+        // The pathfinder sees the hazards once it picks up the change
+        test_utils::run(&mut ship, &object_classes, 1);
+        let cost = |position| ship.tiles.get(position).unwrap().walk_cost(&object_classes)
+            .unwrap().multiplier;
+        assert_eq!(cost(Point2::new(2, 3)), 1.0 + FIRE_HAZARD);
+        assert_eq!(cost(Point2::new(7, 3)), 1.0 + VENTED_HAZARD);
+
+        ship.fires.extinguish(Point2::new(2, 3));
+        test_utils::run(&mut ship, &object_classes, 1);
+        assert_eq!(hazard(&ship, Point2::new(2, 3)), 0.0);
+    }
+
+    #[test]
+    fn paths_go_around_fires() {
+        let object_classes = test_utils::object_classes();
+        // A wall through the middle with a gap at the top and at the bottom, big enough that the
+        // pathfinder's cached clusters have to be updated for the path to change
+        let mut ship = test_utils::hull_ship(Vector2::new(40, 30));
+        for y in 2..28 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(20, y), WALL);
+        }
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        let (start, goal) = (Point2::new(10, 15), Point2::new(30, 15));
+        let find_path = |ship: &Ship| ship.pathfinder()
+            .find_path(start, goal, true, 1.0, &ship.tiles, &object_classes)
+            .unwrap();
+        let path = find_path(&ship);
+        let (taken, other) = if path.contains(&Point2::new(20, 1)) { (1, 28) } else { (28, 1) };
+
+        // The fire needs one update to become a hazard, and one for the pathfinder to see it
+        for x in 19..22 {
+            ship.fires.ignite(Point2::new(x, taken), 1.0);
+        }
+        test_utils::run(&mut ship, &object_classes, 2);
+        let path = find_path(&ship);
+        assert!(!path.contains(&Point2::new(20, taken)));
+        assert!(path.contains(&Point2::new(20, other)));
+    }
+}
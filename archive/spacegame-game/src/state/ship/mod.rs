@@ -1,30 +1,58 @@
+mod atmosphere;
+mod crew;
+mod damage;
+mod enclosure;
+mod fire;
+mod hazard;
+mod overlay;
+mod reservations;
+mod rooms;
 mod ship;
 mod tasks;
 mod unit;
 
 pub use self::{
+    atmosphere::{Atmosphere},
+    crew::{CrewMember, Role, Skill, Skills, Job, MAX_SKILL_LEVEL},
+    damage::{DamageSource},
+    enclosure::{Enclosure},
+    fire::{Fires},
+    overlay::{Overlay, OverlayValue},
+    reservations::{Reservations, Reservation},
+    rooms::{Rooms, RoomId},
     ship::{Ship},
-    tasks::{TaskQueue, TaskId, Task},
-    unit::{Unit},
+    tasks::{TaskQueue, TaskId, Task, TaskKind, UnreachableReason, MIN_EVA_LEVEL},
+    unit::{Unit, UnitId, Order, GiveUpReason, Facing, Activity, MAX_UNIT_HEALTH},
 };
 
 use {
     metrohash::{MetroHashMap},
 
     object_class::{ObjectClassId, ObjectClasses},
-    pathfinding::{Walkable},
+    pathfinding::{Walkable, WalkCost},
     Error,
 };
 
 #[derive(Deserialize, Serialize)]
 pub struct Tile {
-    pub floor: bool,
+    pub layer: TileLayer,
+    /// How much damage the layer has taken, it's breached once this reaches its max health.
+    pub damage: f32,
     pub object: Option<Object>,
+    /// Extra walk cost multiplier from what's going on around the tile, such as fires or a room
+    /// open to space. Kept up to date by the ship, so it's not saved.
+    #[serde(skip)]
+    pub hazard: f32,
 }
 
 impl Tile {
+    /// If the layer has been damaged so much it has a hole in it, open to space.
+    pub fn is_breached(&self) -> bool {
+        self.layer != TileLayer::Space && self.damage >= self.layer.max_health()
+    }
+
     pub fn walkable(&self, object_classes: &ObjectClasses) -> Result<Walkable, Error> {
-        if !self.floor {
+        if self.layer != TileLayer::Floor || self.is_breached() {
             return Ok(Walkable::Never)
         }
 
@@ -39,13 +67,62 @@ impl Tile {
             Walkable::Always
         })
     }
+
+    pub fn walk_cost(&self, object_classes: &ObjectClasses) -> Result<WalkCost, Error> {
+        let mut cost = if let Some(ref object) = self.object {
+            let class = object_classes.get(object.class)?;
+            if let Some(ref behavior) = class.behavior {
+                behavior.walk_cost()
+            } else {
+                WalkCost::normal()
+            }
+        } else {
+            WalkCost::normal()
+        };
+
+        // Hazards only make units prefer going around, they can still walk through if they have to
+        cost.multiplier += self.hazard;
+        Ok(cost)
+    }
 }
 
 impl Default for Tile {
     fn default() -> Self {
         Tile {
-            floor: false,
+            layer: TileLayer::Space,
+            damage: 0.0,
             object: None,
+            hazard: 0.0,
+        }
+    }
+}
+
+/// What a tile is made of, underneath any object on it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TileLayer {
+    /// Nothing at all, open to vacuum.
+    Space,
+    /// Outer plating, can't be walked on but keeps the atmosphere in.
+    Hull,
+    /// Interior floor, only holds atmosphere when enclosed by hull.
+    Floor,
+}
+
+impl TileLayer {
+    pub fn describe(self) -> &'static str {
+        match self {
+            TileLayer::Space => "Space",
+            TileLayer::Hull => "Hull",
+            TileLayer::Floor => "Floor",
+        }
+    }
+
+    /// How much damage the layer can take before it's breached.
+    pub fn max_health(self) -> f32 {
+        match self {
+            TileLayer::Space => 0.0,
+            TileLayer::Hull => 100.0,
+            TileLayer::Floor => 50.0,
         }
     }
 }
@@ -54,6 +131,9 @@ impl Default for Tile {
 pub struct Object {
     pub class: ObjectClassId,
     pub values: MetroHashMap<String, f32>,
+    /// How much damage the object has taken, it's destroyed once this reaches its class's max
+    /// health.
+    pub damage: f32,
 }
 
 impl Object {
@@ -61,6 +141,7 @@ impl Object {
         Object {
             class,
             values: MetroHashMap::default(),
+            damage: 0.0,
         }
     }
 }
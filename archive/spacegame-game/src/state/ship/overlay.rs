@@ -0,0 +1,134 @@
+use {
+    cgmath::{Point2},
+
+    object_class::{ObjectClasses},
+    pathfinding::{Walkable},
+    state::ship::{Ship, TileLayer, unit::{UNIT_SPEED}},
+    Error,
+};
+
+/// How many seconds crossing a tile has to take to be shown as the slowest on the path cost
+/// overlay.
+const MAX_SHOWN_SECONDS: f32 = 2.0;
+
+/// A per-tile value the renderer can tint tiles by, to see what the simulation sees.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Overlay {
+    Rooms,
+    Walkability,
+    PathCost,
+    Enclosure,
+    Oxygen,
+}
+
+impl Overlay {
+    pub fn all() -> &'static [Overlay] {
+        &[
+            Overlay::Rooms, Overlay::Walkability, Overlay::PathCost, Overlay::Enclosure,
+            Overlay::Oxygen,
+        ]
+    }
+
+    /// Goes to the next overlay after the current one, with no overlay after the last one.
+    pub fn cycle(current: Option<Overlay>) -> Option<Overlay> {
+        let all = Overlay::all();
+        match current {
+            Some(overlay) => {
+                let index = all.iter().position(|o| *o == overlay).unwrap();
+                all.get(index + 1).cloned()
+            },
+            None => all.first().cloned(),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Overlay::Rooms => "Rooms",
+            Overlay::Walkability => "Walkability",
+            Overlay::PathCost => "Path Cost",
+            Overlay::Enclosure => "Enclosure",
+            Overlay::Oxygen => "Oxygen",
+        }
+    }
+
+    /// Gets the value to show for a tile, or None if the tile shouldn't be tinted.
+    pub fn value(
+        self, position: Point2<i32>, ship: &Ship, object_classes: &ObjectClasses,
+    ) -> Result<Option<OverlayValue>, Error> {
+        let tile = ship.tiles.get(position)?;
+        if tile.layer == TileLayer::Space {
+            return Ok(None)
+        }
+
+        Ok(match self {
+            Overlay::Rooms =>
+                ship.rooms().room_at(position).map(|room| OverlayValue::Category(room.0)),
+            Overlay::Walkability => Some(OverlayValue::Category(
+                match tile.walkable(object_classes)? {
+                    Walkable::Always => 0,
+                    Walkable::Openable => 1,
+                    Walkable::Never => 2,
+                }
+            )),
+            Overlay::PathCost => {
+                let cost = tile.walk_cost(object_classes)?;
+                if tile.walkable(object_classes)? == Walkable::Never {
+                    None
+                } else {
+                    let seconds = cost.multiplier / UNIT_SPEED + cost.delay;
+                    Some(OverlayValue::Scale((seconds / MAX_SHOWN_SECONDS).min(1.0)))
+                }
+            },
+            Overlay::Enclosure => Some(OverlayValue::Category(
+                match tile.layer {
+                    TileLayer::Hull if !tile.is_breached() => 1,
+                    _ if ship.enclosure().is_enclosed(position) => 0,
+                    _ => 2,
+                }
+            )),
+            Overlay::Oxygen => ship.rooms().room_at(position)
+                .map(|_| OverlayValue::Scale(1.0 - ship.atmosphere.oxygen(position))),
+        })
+    }
+
+    /// Gets example values with what they mean, to explain the overlay.
+    pub fn legend(self) -> Vec<(OverlayValue, String)> {
+        match self {
+            Overlay::Rooms => vec!(
+                (OverlayValue::Category(0), "Each room has its own color".into()),
+                (OverlayValue::Category(1), "Walls and doors separate rooms".into()),
+            ),
+            Overlay::Walkability => vec!(
+                (OverlayValue::Category(0), "Walkable".into()),
+                (OverlayValue::Category(1), "Has to be opened".into()),
+                (OverlayValue::Category(2), "Blocked".into()),
+            ),
+            Overlay::PathCost => vec!(
+                (OverlayValue::Scale(0.0), "Instant".into()),
+                (
+                    OverlayValue::Scale((1.0 / UNIT_SPEED) / MAX_SHOWN_SECONDS),
+                    format!("Bare floor, {:.2}s", 1.0 / UNIT_SPEED),
+                ),
+                (OverlayValue::Scale(1.0), format!("{:.1}s or slower", MAX_SHOWN_SECONDS)),
+            ),
+            Overlay::Enclosure => vec!(
+                (OverlayValue::Category(0), "Enclosed, holds atmosphere".into()),
+                (OverlayValue::Category(1), "Hull".into()),
+                (OverlayValue::Category(2), "Exposed, needs EVA".into()),
+            ),
+            Overlay::Oxygen => vec!(
+                (OverlayValue::Scale(0.0), "Breathable".into()),
+                (OverlayValue::Scale(0.5), "Thin".into()),
+                (OverlayValue::Scale(1.0), "Vacuum".into()),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OverlayValue {
+    /// One of a set of distinct values, such as which room a tile is in.
+    Category(u32),
+    /// A value from 0.0 to 1.0, such as how slow a tile is to walk over.
+    Scale(f32),
+}
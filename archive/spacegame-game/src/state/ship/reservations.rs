@@ -0,0 +1,99 @@
+use {
+    cgmath::{Point2},
+};
+
+/// Which tiles units are standing on and which tiles they want to move into next, used by units
+/// to avoid walking into each other.
+pub struct Reservations {
+    units: Vec<Reservation>,
+}
+
+impl Reservations {
+    pub fn new() -> Self {
+        Reservations {
+            units: Vec::new(),
+        }
+    }
+
+    pub fn set(&mut self, unit_index: usize, reservation: Reservation) {
+        if self.units.len() <= unit_index {
+            self.units.resize(unit_index + 1, reservation);
+        }
+
+        self.units[unit_index] = reservation;
+    }
+
+    /// Finds another unit that's standing on or moving into the tile.
+    pub fn blocking(&self, unit_index: usize, tile: Point2<i32>) -> Option<Reservation> {
+        self.units.iter().enumerate()
+            .filter(|&(index, _)| index != unit_index)
+            .map(|(_, reservation)| *reservation)
+            .find(|reservation| reservation.tile == tile || reservation.next == Some(tile))
+    }
+
+    /// Checks if the unit can move from its tile into the next one by swapping places with the
+    /// unit standing there. Both units have to start moving in the same update or they'd end up
+    /// on the same tile halfway, so the unit that updates first only goes once the other is
+    /// waiting for its tile, and the other follows right after.
+    pub fn is_swapping(&self, unit_index: usize, tile: Point2<i32>, next: Point2<i32>) -> bool {
+        self.units.iter().enumerate()
+            .filter(|&(index, reservation)| index != unit_index && reservation.tile == next)
+            .any(|(index, reservation)| reservation.next == Some(tile) ||
+                (index > unit_index && reservation.wants == Some(tile)))
+    }
+
+    /// Checks if another unit that came before this one is standing still on the tile.
+    pub fn is_shared(&self, unit_index: usize, tile: Point2<i32>) -> bool {
+        self.units.iter()
+            .take(unit_index)
+            .any(|reservation| reservation.tile == tile && reservation.next.is_none())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Reservation {
+    pub tile: Point2<i32>,
+    /// The tile the unit is moving into.
+    pub next: Option<Point2<i32>>,
+    /// The tile the unit is waiting to move into.
+    pub wants: Option<Point2<i32>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        state::ship::{Ship, Order},
+        test_utils,
+    };
+
+    #[test]
+    fn units_pass_each_other_in_corridor() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(12, 3));
+
+        let (left, right) = (Point2::new(1, 1), Point2::new(10, 1));
+        let a = test_utils::add_unit(&mut ship, left);
+        let b = test_utils::add_unit(&mut ship, right);
+        ship.give_order(&log, a, Order::MoveTo(right)).unwrap();
+        ship.give_order(&log, b, Order::MoveTo(left)).unwrap();
+
+        let arrived = |ship: &Ship, id, goal| {
+            let unit = ship.unit(id).unwrap();
+            unit.tile_position() == goal && unit.path().is_none()
+        };
+
+        let mut ticks = 0;
+        while !arrived(&ship, a, right) || !arrived(&ship, b, left) {
+            assert!(ticks < 200, "Units didn't get past each other in time");
+            test_utils::run(&mut ship, &object_classes, 1);
+            ticks += 1;
+
+            assert_ne!(
+                ship.unit(a).unwrap().tile_position(), ship.unit(b).unwrap().tile_position(),
+            );
+        }
+    }
+}
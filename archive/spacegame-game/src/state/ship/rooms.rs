@@ -0,0 +1,52 @@
+use {
+    cgmath::{Point2},
+
+    lagato::{grid::{Tiles}},
+
+    flood::{Labels},
+    object_class::{ObjectClasses},
+    pathfinding::{Walkable},
+    state::ship::{Tile},
+    Error,
+};
+
+/// Connected areas of open floor, closed off from each other by walls and doors.
+pub struct Rooms {
+    labels: Labels,
+}
+
+impl Rooms {
+    pub fn new() -> Self {
+        Rooms {
+            labels: Labels::new(),
+        }
+    }
+
+    pub fn amount(&self) -> u32 {
+        self.labels.amount()
+    }
+
+    /// Labels all tiles with the room they're in.
+    pub fn rebuild(
+        &mut self, tiles: &Tiles<Tile>, object_classes: &ObjectClasses,
+    ) -> Result<(), Error> {
+        self.labels = Labels::build(tiles, |tile| {
+            Ok(tile.walkable(object_classes)? == Walkable::Always)
+        })?;
+
+        Ok(())
+    }
+
+    pub fn room_at(&self, position: Point2<i32>) -> Option<RoomId> {
+        self.labels.label_at(position).map(RoomId)
+    }
+}
+
+impl Default for Rooms {
+    fn default() -> Self {
+        Rooms::new()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct RoomId(pub u32);
@@ -1,72 +1,229 @@
 use {
-    cgmath::{Point2, Vector2},
+    cgmath::{Point2, Vector2, MetricSpace},
     slog::{Logger},
 
     lagato::{grid::{Tiles}},
 
     object_class::{ObjectClasses},
-    state::ship::{Unit, Tile, TaskQueue},
+    pathfinding::{Pathfinder, PathRequests},
+    state::ship::{
+        Unit, UnitId, Order, Tile, TileLayer, TaskId, TaskQueue, Reservations, Rooms, Enclosure,
+        Atmosphere, Fires, CrewMember, Role,
+    },
     Error,
 };
 
 #[derive(Deserialize, Serialize)]
 pub struct Ship {
     units: Vec<Unit>,
+    next_unit_id: u32,
 
     pub tiles: Tiles<Tile>,
     pub task_queue: TaskQueue,
+    pub atmosphere: Atmosphere,
+    pub fires: Fires,
 
     tiles_with_behaviors: Vec<Point2<i32>>,
-    tiles_changed: bool,
+    #[serde(skip)]
+    pathfinder: Pathfinder,
+    #[serde(skip)]
+    rooms: Rooms,
+    #[serde(skip)]
+    enclosure: Enclosure,
+    path_requests: PathRequests,
 }
 
 impl Ship {
     pub fn empty(size: Vector2<i32>) -> Self {
         Ship {
             units: Vec::new(),
+            next_unit_id: 0,
 
             tiles: Tiles::empty(size),
             task_queue: TaskQueue::new(),
+            atmosphere: Atmosphere::new(size),
+            fires: Fires::new(size),
 
             tiles_with_behaviors: Vec::new(),
-            tiles_changed: false,
+            pathfinder: Pathfinder::new(),
+            rooms: Rooms::new(),
+            enclosure: Enclosure::new(),
+            path_requests: PathRequests::new(),
         }
     }
 
     pub fn starter(log: &Logger) -> Self {
         info!(log, "Creating starter ship");
         let mut ship = Ship::empty(Vector2::new(100, 100));
-        for y in 47..53 {
-            for x in 48..52 {
-                ship.tiles.get_mut(Point2::new(x, y)).unwrap().floor = true;
+        for y in 46..54 {
+            for x in 47..53 {
+                // Floor surrounded by a ring of hull
+                let edge = y == 46 || y == 53 || x == 47 || x == 52;
+                ship.tiles.get_mut(Point2::new(x, y)).unwrap().layer =
+                    if edge { TileLayer::Hull } else { TileLayer::Floor };
             }
         }
-        ship.add_unit(Unit::new(Point2::new(50.5, 50.5)));
-        ship.add_unit(Unit::new(Point2::new(49.5, 49.5)));
+        ship.add_unit(Unit::new(
+            Point2::new(50.5, 50.5), CrewMember::new("Ada Marsh".into(), Role::Engineer),
+        ));
+        ship.add_unit(Unit::new(
+            Point2::new(49.5, 49.5), CrewMember::new("Tomas Reyes".into(), Role::Hauler),
+        ));
 
         ship
     }
 
+    pub fn pathfinder(&self) -> &Pathfinder {
+        &self.pathfinder
+    }
+
+    pub fn pathfinder_mut(&mut self) -> &mut Pathfinder {
+        &mut self.pathfinder
+    }
+
+    pub fn rooms(&self) -> &Rooms {
+        &self.rooms
+    }
+
+    pub fn enclosure(&self) -> &Enclosure {
+        &self.enclosure
+    }
+
     pub fn units(&self) -> &Vec<Unit> {
         &self.units
     }
 
-    pub fn add_unit(&mut self, unit: Unit) {
+    pub fn units_mut(&mut self) -> &mut Vec<Unit> {
+        &mut self.units
+    }
+
+    pub fn unit(&self, id: UnitId) -> Option<&Unit> {
+        self.units.iter().find(|unit| unit.id() == id)
+    }
+
+    pub fn unit_mut(&mut self, id: UnitId) -> Option<&mut Unit> {
+        self.units.iter_mut().find(|unit| unit.id() == id)
+    }
+
+    pub fn add_unit(&mut self, mut unit: Unit) -> UnitId {
+        let id = UnitId(self.next_unit_id);
+        self.next_unit_id += 1;
+
+        unit.set_id(id);
         self.units.push(unit);
+
+        id
+    }
+
+    /// Finds the unit closest to the position, within the given distance.
+    pub fn unit_at(&self, position: Point2<f32>, max_distance: f32) -> Option<UnitId> {
+        let mut found = None;
+        let mut found_distance2 = max_distance * max_distance;
+
+        for unit in &self.units {
+            let distance2 = unit.position().distance2(position);
+            if distance2 <= found_distance2 {
+                found = Some(unit.id());
+                found_distance2 = distance2;
+            }
+        }
+
+        found
+    }
+
+    /// Finds all units within the area, start and end can be any two opposite corners.
+    pub fn units_in_area(&self, start: Point2<f32>, end: Point2<f32>) -> Vec<UnitId> {
+        let (min_x, max_x) = (start.x.min(end.x), start.x.max(end.x));
+        let (min_y, max_y) = (start.y.min(end.y), start.y.max(end.y));
+
+        self.units.iter()
+            .filter(|unit| {
+                let position = unit.position();
+                position.x >= min_x && position.x <= max_x &&
+                position.y >= min_y && position.y <= max_y
+            })
+            .map(|unit| unit.id())
+            .collect()
+    }
+
+    /// Remembers where all units are before a game update, so their movement can be smoothed out
+    /// when drawing.
+    pub fn remember_positions(&mut self) {
+        for unit in &mut self.units {
+            unit.remember_position();
+        }
+    }
+
+    /// Stops updating the behavior of the object on a tile right away, for objects that got
+    /// removed before the tiles get checked for changes again.
+    pub fn forget_behavior(&mut self, position: Point2<i32>) {
+        self.tiles_with_behaviors.retain(|behavior_position| *behavior_position != position);
+    }
+
+    /// Removes a task from the queue, and stops the unit working on it if there is one so it
+    /// doesn't keep going to a task that's not there anymore.
+    pub fn cancel_task(&mut self, task_id: TaskId) -> Result<(), Error> {
+        self.task_queue.dequeue(task_id)?;
+
+        for unit in &mut self.units {
+            if unit.assigned_task() == Some(task_id) {
+                unit.drop_actions(&mut self.task_queue, &mut self.path_requests);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn give_order(&mut self, log: &Logger, id: UnitId, order: Order) -> Result<(), Error> {
+        if self.unit(id).is_none() {
+            return Err(Error::InvalidUnitId(id))
+        }
+
+        // Only one unit can work on a task, so take it away from whoever has it now
+        if let Order::PrioritizeTask(task_id) = order {
+            for unit in &mut self.units {
+                if unit.id() != id && unit.assigned_task() == Some(task_id) {
+                    unit.drop_actions(&mut self.task_queue, &mut self.path_requests);
+                }
+            }
+        }
+
+        let unit = self.units.iter_mut().find(|unit| unit.id() == id).unwrap();
+        unit.give_order(log, order, &mut self.task_queue, &mut self.path_requests);
+
+        Ok(())
     }
 
     pub fn update(
         &mut self, log: &Logger, object_classes: &ObjectClasses, delta: f32,
     ) -> Result<(), Error> {
-        if self.handle_tiles_changed(object_classes)? {
-            // Since the world has changed, we can mark all tasks as being possible again
-            self.task_queue.clear_unreachable();
+        if let Some(changed) = self.handle_tiles_changed(object_classes)? {
+            // Since the world has changed, check again which tasks any of our units can get to
+            let unit_positions: Vec<_> = self.units.iter()
+                .map(|unit| unit.tile_position())
+                .collect();
+            self.task_queue.update_reachability(self.pathfinder.regions(), &unit_positions);
+
+            // Let units know in case they have to find another way
+            for unit in &mut self.units {
+                unit.handle_tiles_changed(log, &changed);
+            }
         }
 
+        // Tasks can be added at any time, so this can't wait for the tiles to change
+        self.task_queue.update_enclosure(&self.enclosure);
+
+        self.update_fires(log, object_classes, delta)?;
+        self.atmosphere.update(&self.tiles, &self.rooms, &self.enclosure, &self.fires, delta);
+        self.update_hazards()?;
+
         for i in &self.tiles_with_behaviors {
-            let object = self.tiles.get_mut(*i)?
-                .object.as_mut()
-                    .expect("Found tile without object in tiles with behaviors");
+            // Objects can get destroyed between rebuilding the list and now
+            let object = if let Some(ref mut object) = self.tiles.get_mut(*i)?.object {
+                object
+            } else {
+                continue
+            };
             let behavior = object_classes.get(object.class)?
                 .behavior.as_ref()
                     .expect("Found tile class without behavior in tiles with behaviors");
@@ -74,17 +231,38 @@ impl Ship {
             behavior.update(object, delta);
         }
 
-        for unit in &mut self.units {
-            unit.update(log, object_classes, &mut self.tiles, &mut self.task_queue, delta)?;
+        // Keep track of where units are so they can avoid each other
+        let mut reservations = Reservations::new();
+        for (index, unit) in self.units.iter().enumerate() {
+            reservations.set(index, unit.reservation());
         }
 
+        for (index, unit) in self.units.iter_mut().enumerate() {
+            unit.update(
+                log, object_classes,
+                &mut self.tiles, &mut self.task_queue,
+                &self.pathfinder, &mut self.path_requests,
+                &reservations, index,
+                delta,
+            )?;
+            reservations.set(index, unit.reservation());
+        }
+
+        // Find some of the paths units asked for, they'll get them next update
+        self.path_requests.update(&self.pathfinder, &self.tiles, object_classes);
+
         self.task_queue.update(log);
 
         Ok(())
     }
 
-    fn handle_tiles_changed(&mut self, object_classes: &ObjectClasses) -> Result<bool, Error> {
-        Ok(if self.tiles_changed {
+    /// Updates everything that depends on the tiles if they have changed, returns the positions of
+    /// tiles that changed in a way that matters for pathfinding.
+    fn handle_tiles_changed(
+        &mut self, object_classes: &ObjectClasses,
+    ) -> Result<Option<Vec<Point2<i32>>>, Error> {
+        // The pathfinder isn't saved, so after loading we need to build it again
+        Ok(if self.tiles.changed.check() || !self.pathfinder.is_built() {
             // Find any tiles that ask for update events
             self.tiles_with_behaviors.clear();
             for position in self.tiles.iter_pos() {
@@ -97,10 +275,13 @@ impl Ship {
                 }
             }
 
-            self.tiles_changed = false;
-            true
+            self.rooms.rebuild(&self.tiles, object_classes)?;
+            self.enclosure.rebuild(&self.tiles, object_classes)?;
+
+            // Bring the pathfinding data up to date with the new tiles
+            Some(self.pathfinder.handle_tiles_changed(&self.tiles, object_classes)?)
         } else {
-            false
+            None
         })
     }
 }
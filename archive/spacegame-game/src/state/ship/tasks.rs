@@ -3,10 +3,17 @@ use {
     metrohash::{MetroHashMap},
     slog::{Logger},
 
-    object_class::{ObjectClassId},
+    object_class::{ObjectClassId, ObjectClasses, SkillRequirement},
+    pathfinding::{Regions},
+    state::ship::{CrewMember, Job, Skill, Skills, Enclosure},
     Error,
 };
 
+/// Skill needed to do any work outside of the hull.
+pub const MIN_EVA_LEVEL: u32 = 1;
+/// How many seconds of work at normal skill it takes to repair a tile and its object.
+const REPAIR_WORK: f32 = 3.0;
+
 #[derive(Deserialize, Serialize)]
 pub struct TaskQueue {
     // Faster non-crypto hasher for small & medium key sizes
@@ -59,24 +66,52 @@ impl TaskQueue {
         Ok(())
     }
 
-    pub fn assign(&mut self, log: &Logger, closest_to: Point2<f32>) -> Option<TaskId> {
+    /// Raises the priority of a task, so it gets picked over lower priority tasks.
+    pub fn raise_priority(&mut self, id: TaskId) -> Result<(), Error> {
+        let task = self.tasks.get_mut(&id)
+            .ok_or(Error::InvalidTaskId(id))?;
+        task.priority += 1;
+
+        Ok(())
+    }
+
+    /// Assigns the highest priority task the crew member is allowed to do and can get to,
+    /// preferring the closest one if there's multiple.
+    pub fn assign(
+        &mut self, log: &Logger, closest_to: Point2<f32>, crew: &CrewMember, regions: &Regions,
+    ) -> Option<TaskId> {
+        let mut found_priority = 0;
         let mut found_distance_squared = ::std::f32::INFINITY;
         let mut found_task = None;
+        let closest_to_tile = Point2::new(closest_to.x as i32, closest_to.y as i32);
 
         // Find the closest valid task
         for (key, task) in &mut self.tasks {
             // We don't want a task that's already assigned, or one we can't reach
-            if task.assigned || task.unreachable {
+            if task.assigned || task.unreachable.is_some() {
                 continue
             }
 
-            // Check if this task is closer than what we found
+            // The player may not want this crew member to do this kind of work, or they may not
+            // be skilled enough for it
+            if !crew.is_job_enabled(task.job) || !task.can_be_done_by(crew) {
+                continue
+            }
+
+            // If the task isn't connected to where we are, we don't even need to try pathfinding
+            if !regions.can_reach(closest_to_tile, task.position, false) {
+                continue
+            }
+
+            // Check if this task is more important or closer than what we found
             let task_center = Point2::new(
                 task.position.x as f32 + 0.5,
                 task.position.y as f32 + 0.5
             );
             let distance_squared = closest_to.distance2(task_center);
-            if distance_squared < found_distance_squared {
+            if found_task.is_none() || task.priority > found_priority ||
+               (task.priority == found_priority && distance_squared < found_distance_squared) {
+                found_priority = task.priority;
                 found_distance_squared = distance_squared;
                 found_task = Some(*key)
             }
@@ -85,15 +120,27 @@ impl TaskQueue {
         // If we found a task, assign it
         if let Some(task_id) = found_task {
             self.get_mut(task_id).unwrap().assigned = true;
-            info!(log, "Assigned task {}", task_id.0);
+            info!(log, "Assigned task {} to {}", task_id.0, crew.name);
         }
 
         found_task
     }
 
-    pub fn clear_unreachable(&mut self) {
+    /// Marks tasks as unreachable if none of the given positions are connected to them, and
+    /// marks all other tasks as possible again.
+    pub fn update_reachability(&mut self, regions: &Regions, unit_positions: &[Point2<i32>]) {
         for (_, task) in &mut self.tasks {
-            task.unreachable = false;
+            let reachable = unit_positions.iter()
+                .any(|position| regions.can_reach(*position, task.position, false));
+            task.unreachable = if reachable { None } else { Some(UnreachableReason::Disconnected) };
+        }
+    }
+
+    /// Marks tasks that can't be worked on from inside the hull, so only crew that can do EVA
+    /// work take them.
+    pub fn update_enclosure(&mut self, enclosure: &Enclosure) {
+        for (_, task) in &mut self.tasks {
+            task.needs_eva = !enclosure.is_workable_from_inside(task.position);
         }
     }
 
@@ -116,35 +163,300 @@ impl TaskQueue {
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, Deserialize, Serialize)]
 pub struct TaskId(pub u32);
 
+/// Why no unit is able to get to a task.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum UnreachableReason {
+    /// None of the units are in an area connected to the task.
+    Disconnected,
+    /// A unit tried to find a path to the task but couldn't.
+    NoPath,
+}
+
+/// What should happen at a task's position once the work is done.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TaskKind {
+    /// Place a new object of the class.
+    Build(ObjectClassId),
+    /// Fix all damage to the tile and the object on it.
+    Repair,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Task {
     pub position: Point2<i32>,
-    pub object_class: ObjectClassId,
+    pub kind: TaskKind,
+    pub job: Job,
+    pub requirement: Option<SkillRequirement>,
+    /// Tasks with a higher priority get picked first.
+    pub priority: u32,
     pub assigned: bool,
-    pub unreachable: bool,
+    pub unreachable: Option<UnreachableReason>,
+    /// If the task is outside of the hull, and needs someone in a suit.
+    pub needs_eva: bool,
 
     work_done: f32,
     work_target: f32,
 }
 
 impl Task {
-    pub fn new(position: Point2<i32>, object_class: ObjectClassId, work_target: f32) -> Self {
+    /// Creates a task to build an object of the class at the position.
+    pub fn new(
+        position: Point2<i32>, object_class: ObjectClassId, object_classes: &ObjectClasses,
+    ) -> Result<Self, Error> {
+        let class = object_classes.get(object_class)?;
+
+        Ok(Task {
+            position,
+            kind: TaskKind::Build(object_class),
+            job: Job::Build,
+            requirement: class.build_requirement,
+            priority: 0,
+            assigned: false,
+            unreachable: None,
+            needs_eva: false,
+
+            work_done: 0.0,
+            work_target: class.build_work,
+        })
+    }
+
+    /// Creates a task to repair the tile at the position.
+    pub fn repair(position: Point2<i32>) -> Self {
         Task {
             position,
-            object_class,
+            kind: TaskKind::Repair,
+            job: Job::Repair,
+            requirement: None,
+            priority: 0,
             assigned: false,
-            unreachable: false,
+            unreachable: None,
+            needs_eva: false,
 
             work_done: 0.0,
-            work_target,
+            work_target: REPAIR_WORK,
+        }
+    }
+
+    /// What kind of task this is, for showing to the player.
+    pub fn describe(&self, object_classes: &ObjectClasses) -> String {
+        let description = match self.kind {
+            TaskKind::Build(object_class) => {
+                let name = object_classes.get(object_class)
+                    .map(|class| class.friendly_name.as_str())
+                    .unwrap_or("unknown object");
+                format!("Build {}", name)
+            },
+            TaskKind::Repair => "Repair damage".into(),
+        };
+
+        if self.needs_eva {
+            format!("{} (EVA)", description)
+        } else {
+            description
+        }
+    }
+
+    pub fn can_be_done_by(&self, crew: &CrewMember) -> bool {
+        if self.needs_eva && crew.skills.level(Skill::Eva) < MIN_EVA_LEVEL {
+            return false
+        }
+
+        self.requirement
+            .map(|requirement| crew.skills.level(requirement.skill) >= requirement.level)
+            .unwrap_or(true)
+    }
+
+    /// How far along the work is, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.work_target <= 0.0 {
+            1.0
+        } else {
+            (self.work_done / self.work_target).min(1.0)
         }
     }
 
-    pub fn apply_work(&mut self, amount: f32) {
-        self.work_done += amount;
+    /// Applies an amount of seconds of work, sped up or slowed down by the worker's skill.
+    pub fn apply_work(&mut self, seconds: f32, skills: &Skills) {
+        self.work_done += seconds * skills.work_speed(self.job.skill());
     }
 
     pub fn is_done(&self) -> bool {
         self.work_done > self.work_target
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        object_class::{SkillRequirement},
+        state::ship::{
+            Ship, TaskQueue, Task, CrewMember, Role, Job, Skill, UnreachableReason, DamageSource,
+            TileLayer,
+        },
+        test_utils::{self, DOOR, WALL},
+    };
+
+    fn built_ship() -> Ship {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 5));
+        test_utils::run(&mut ship, &object_classes, 1);
+        ship
+    }
+
+    fn crew(role: Role) -> CrewMember {
+        CrewMember::new("Test Crew".into(), role)
+    }
+
+    #[test]
+    fn assign_skips_disabled_jobs() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let ship = built_ship();
+        let mut queue = TaskQueue::new();
+        queue.queue(Task::new(Point2::new(6, 2), DOOR, &object_classes).unwrap()).unwrap();
+
+        let mut engineer = crew(Role::Engineer);
+        engineer.set_job_enabled(Job::Build, false);
+        let regions = ship.pathfinder().regions();
+        assert_eq!(queue.assign(&log, Point2::new(1.5, 2.5), &engineer, regions), None);
+
+        engineer.set_job_enabled(Job::Build, true);
+        assert!(queue.assign(&log, Point2::new(1.5, 2.5), &engineer, regions).is_some());
+    }
+
+    #[test]
+    fn assign_prefers_raised_priority_over_distance() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let ship = built_ship();
+        let mut queue = TaskQueue::new();
+        queue.queue(Task::new(Point2::new(2, 2), WALL, &object_classes).unwrap()).unwrap();
+        queue.queue(Task::new(Point2::new(7, 2), WALL, &object_classes).unwrap()).unwrap();
+        let near = queue.get_at(Point2::new(2, 2)).unwrap();
+        let far = queue.get_at(Point2::new(7, 2)).unwrap();
+        queue.raise_priority(far).unwrap();
+
+        let engineer = crew(Role::Engineer);
+        let regions = ship.pathfinder().regions();
+        assert_eq!(queue.assign(&log, Point2::new(1.5, 2.5), &engineer, regions), Some(far));
+        assert_eq!(queue.assign(&log, Point2::new(1.5, 2.5), &engineer, regions), Some(near));
+        assert_eq!(queue.assign(&log, Point2::new(1.5, 2.5), &engineer, regions), None);
+    }
+
+    #[test]
+    fn requirements_limit_who_can_do_tasks() {
+        let object_classes = test_utils::object_classes();
+        let mut task = Task::new(Point2::new(6, 2), DOOR, &object_classes).unwrap();
+        task.requirement = Some(SkillRequirement { skill: Skill::Construction, level: 5 });
+
+        assert!(task.can_be_done_by(&crew(Role::Engineer)));
+        assert!(!task.can_be_done_by(&crew(Role::Medic)));
+
+        // Medics can't go outside at all
+        let mut task = Task::repair(Point2::new(0, 2));
+        task.needs_eva = true;
+        assert!(task.can_be_done_by(&crew(Role::Engineer)));
+        assert!(!task.can_be_done_by(&crew(Role::Medic)));
+    }
+
+    #[test]
+    fn assign_waits_for_regions_to_join() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 5));
+        for y in 1..4 {
+            test_utils::place(&mut ship, &object_classes, Point2::new(4, y), WALL);
+        }
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        let mut queue = TaskQueue::new();
+        queue.queue(Task::new(Point2::new(6, 2), DOOR, &object_classes).unwrap()).unwrap();
+        let engineer = crew(Role::Engineer);
+        let assign = |queue: &mut TaskQueue, ship: &Ship| queue.assign(
+            &log, Point2::new(1.5, 2.5), &engineer, ship.pathfinder().regions(),
+        );
+
+        // Assigning checks the regions itself, even before the task is marked as unreachable
+        let task = queue.get_at(Point2::new(6, 2)).unwrap();
+        assert_eq!(assign(&mut queue, &ship), None);
+        queue.update_reachability(ship.pathfinder().regions(), &[Point2::new(1, 2)]);
+        assert_eq!(queue.get(task).unwrap().unreachable, Some(UnreachableReason::Disconnected));
+
+        // A door in the wall joins the two sides
+        test_utils::place(&mut ship, &object_classes, Point2::new(4, 2), DOOR);
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        queue.update_reachability(ship.pathfinder().regions(), &[Point2::new(1, 2)]);
+        assert_eq!(queue.get(task).unwrap().unreachable, None);
+        assert_eq!(assign(&mut queue, &ship), Some(task));
+    }
+
+    #[test]
+    fn skilled_crew_finish_tasks_faster() {
+        let object_classes = test_utils::object_classes();
+        let ticks_to_build = |construction| {
+            let mut ship = test_utils::hull_ship(Vector2::new(9, 5));
+            let position = Point2::new(4, 2);
+            ship.task_queue.queue(Task::new(position, WALL, &object_classes).unwrap()).unwrap();
+            let task = ship.task_queue.get_at(position).unwrap();
+            let unit = test_utils::add_unit(&mut ship, Point2::new(3, 2));
+            ship.unit_mut(unit).unwrap().crew_mut().skills.construction = construction;
+
+            let mut progress = 0.0;
+            for ticks in 1..100 {
+                test_utils::run(&mut ship, &object_classes, 1);
+                let task = if let Some(task) = ship.task_queue.get(task) {
+                    task
+                } else {
+                    return ticks
+                };
+                assert!(task.progress() >= progress);
+                progress = task.progress();
+            }
+            panic!("Task never finished");
+        };
+
+        let novice = ticks_to_build(1);
+        let expert = ticks_to_build(10);
+        assert!(expert < novice, "expert took {} ticks, novice took {}", expert, novice);
+    }
+
+    #[test]
+    fn breaches_are_repaired_from_inside() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        // The hull is closed off at x 6, with a loose piece of hull floating in space past it
+        let mut ship = test_utils::hull_ship(Vector2::new(10, 5));
+        for y in 0..5 {
+            ship.tiles.get_mut(Point2::new(6, y)).unwrap().layer = TileLayer::Hull;
+            for x in 7..10 {
+                ship.tiles.get_mut(Point2::new(x, y)).unwrap().layer = TileLayer::Space;
+            }
+        }
+        ship.tiles.get_mut(Point2::new(9, 2)).unwrap().layer = TileLayer::Hull;
+        let medic = test_utils::add_crew(&mut ship, Point2::new(3, 2), Role::Medic);
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        let breach = Point2::new(0, 2);
+        ship.damage_tile(&log, &object_classes, breach, 1000.0, DamageSource::Meteor).unwrap();
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        let task = ship.task_queue.get_at(breach).unwrap();
+        assert!(!ship.task_queue.get(task).unwrap().needs_eva);
+        assert_eq!(ship.unit(medic).unwrap().assigned_task(), Some(task));
+
+        // Hull that isn't next to the inside of the ship does need someone in a suit
+        let mut queue = TaskQueue::new();
+        queue.queue(Task::repair(Point2::new(6, 2))).unwrap();
+        queue.queue(Task::repair(Point2::new(9, 2))).unwrap();
+        queue.update_enclosure(ship.enclosure());
+        let needs_eva = |position| {
+            let task = queue.get_at(position).unwrap();
+            queue.get(task).unwrap().needs_eva
+        };
+        assert!(!needs_eva(Point2::new(6, 2)));
+        assert!(needs_eva(Point2::new(9, 2)));
+    }
+}
@@ -5,26 +5,79 @@ use {
     lagato::{grid::{Tiles}},
 
     object_class::{ObjectClasses},
-    pathfinding::{self, Walkable},
-    state::ship::{Tile, TaskId, TaskQueue},
+    pathfinding::{Walkable, Pathfinder, PathRequests, PathRequest, PathRequestId},
+    state::ship::{
+        Tile, TaskId, TaskQueue, TaskKind, UnreachableReason, Reservations, Reservation, CrewMember,
+    },
     Error,
 };
 
-const UNIT_SPEED: f32 = 1.5;
+pub const UNIT_SPEED: f32 = 1.5;
+/// How long a unit waits for another unit to get out of the way before walking through it.
+const MAX_WAIT: f32 = 3.0;
+pub const MAX_UNIT_HEALTH: f32 = 100.0;
 
 #[derive(Deserialize, Serialize)]
 pub struct Unit {
+    id: UnitId,
     position: Point2<f32>,
+    /// Where the unit was before the last game update, to smooth out movement when drawing.
+    previous_position: Point2<f32>,
+    facing: Facing,
+    activity: Activity,
+    /// How many seconds the unit has been doing its current activity.
+    activity_time: f32,
+    crew: CrewMember,
+    health: f32,
 
     action_stack: Vec<Action>,
+    last_give_up: Option<GiveUpReason>,
 }
 
 impl Unit {
-    pub fn new(position: Point2<f32>) -> Self {
+    pub fn new(position: Point2<f32>, crew: CrewMember) -> Self {
         Unit {
+            // Assigned by the ship when the unit gets added to it
+            id: UnitId(0),
             position,
+            previous_position: position,
+            facing: Facing::Down,
+            activity: Activity::Idle,
+            activity_time: 0.0,
+            crew,
+            health: MAX_UNIT_HEALTH,
 
             action_stack: vec!(Action::FindTask),
+            last_give_up: None,
+        }
+    }
+
+    pub fn id(&self) -> UnitId {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: UnitId) {
+        self.id = id;
+    }
+
+    pub fn crew(&self) -> &CrewMember {
+        &self.crew
+    }
+
+    pub fn crew_mut(&mut self) -> &mut CrewMember {
+        &mut self.crew
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    /// Hurts the unit, health doesn't go below 0.0.
+    pub fn damage(&mut self, log: &Logger, amount: f32) {
+        let was_healthy = self.health > 0.0;
+        self.health = (self.health - amount).max(0.0);
+        if was_healthy && self.health <= 0.0 {
+            warn!(log, "{} is badly hurt", self.crew.name);
         }
     }
 
@@ -32,16 +85,202 @@ impl Unit {
         self.position
     }
 
+    /// Gets a position between where the unit was before the last game update and where it is
+    /// now, `fraction` being how far along the next update we are.
+    pub fn interpolated_position(&self, fraction: f32) -> Point2<f32> {
+        self.previous_position + (self.position - self.previous_position) * fraction
+    }
+
+    /// Remembers the current position as where the unit was before the next game update.
+    pub fn remember_position(&mut self) {
+        self.previous_position = self.position;
+    }
+
+    pub fn facing(&self) -> Facing {
+        self.facing
+    }
+
+    pub fn activity(&self) -> Activity {
+        self.activity
+    }
+
+    pub fn activity_time(&self) -> f32 {
+        self.activity_time
+    }
+
+    pub fn tile_position(&self) -> Point2<i32> {
+        Point2::new(self.position.x as i32, self.position.y as i32)
+    }
+
+    /// Describes what the unit is doing, from its overall goal down to what it's doing right now.
+    pub fn describe_actions(
+        &self, task_queue: &TaskQueue, object_classes: &ObjectClasses,
+    ) -> Vec<String> {
+        self.action_stack.iter().enumerate()
+            // Looking for a task is only interesting if it's the only thing we're doing
+            .filter(|&(i, action)| match *action {
+                Action::FindTask => i + 1 == self.action_stack.len(),
+                _ => true,
+            })
+            .map(|(_, action)| action.describe(task_queue, object_classes))
+            .collect()
+    }
+
+    /// Describes what the unit is doing in one line.
+    pub fn describe(&self, task_queue: &TaskQueue, object_classes: &ObjectClasses) -> String {
+        self.describe_actions(task_queue, object_classes).join(" - ")
+    }
+
+    /// The task the unit is working on, if any.
+    pub fn assigned_task(&self) -> Option<TaskId> {
+        self.action_stack.iter()
+            .filter_map(|action| if let Action::Work { task_id, .. } = *action {
+                Some(task_id)
+            } else {
+                None
+            })
+            .last()
+    }
+
+    /// The path the unit is currently following, if any, in the same order as `find_path`.
+    pub fn path(&self) -> Option<&Vec<Point2<i32>>> {
+        self.action_stack.iter()
+            .filter_map(|action| if let Action::FollowPath { ref path, .. } = *action {
+                Some(path)
+            } else {
+                None
+            })
+            .last()
+    }
+
+    /// The reason this unit last gave up on something it was doing, if it ever did.
+    pub fn last_give_up(&self) -> Option<GiveUpReason> {
+        self.last_give_up
+    }
+
+    /// The tile this unit is on, and the tile it's moving into or waiting to move into.
+    pub fn reservation(&self) -> Reservation {
+        let tile = self.tile_position();
+        let mut reservation = Reservation {
+            tile,
+            next: None,
+            wants: None,
+        };
+
+        match self.action_stack.iter().last() {
+            Some(&Action::FollowPath { ref path, waited, .. }) => {
+                if let Some(target) = path.iter().last() {
+                    if *target != tile {
+                        reservation.next = Some(*target);
+                    } else if waited > 0.0 && path.len() >= 2 {
+                        reservation.wants = Some(path[path.len() - 2]);
+                    }
+                }
+            },
+            Some(&Action::OpenDoor { target }) => reservation.next = Some(target),
+            _ => {},
+        }
+
+        reservation
+    }
+
+    /// Marks any paths the unit is following as needing to be found again if they go over any of
+    /// the changed tiles.
+    pub fn handle_tiles_changed(&mut self, log: &Logger, changed: &[Point2<i32>]) {
+        for action in &mut self.action_stack {
+            if let Action::FollowPath { ref path, ref mut replan, .. } = *action {
+                if !*replan && path.iter().any(|node| changed.contains(node)) {
+                    info!(log, "Path of unit at {:?} changed, replanning", self.position);
+                    *replan = true;
+                }
+            }
+        }
+    }
+
+    /// Makes the unit follow a direct order, on top of or instead of what it was doing on its own.
+    pub fn give_order(
+        &mut self, log: &Logger, order: Order,
+        task_queue: &mut TaskQueue, path_requests: &mut PathRequests,
+    ) {
+        info!(log, "{} was ordered to {:?}", self.crew.name, order);
+
+        match order {
+            Order::MoveTo(target) => {
+                // Once we've moved, anything we were going to walk has to be found again from
+                // wherever we end up
+                self.resume();
+                self.forget_paths(path_requests);
+                self.action_stack.push(Action::MoveTo { target, path_request: None });
+            },
+            Order::PrioritizeTask(task_id) => {
+                self.drop_actions(task_queue, path_requests);
+                if let Some(task) = task_queue.get_mut(task_id) {
+                    task.assigned = true;
+                    self.action_stack.push(Action::Work { task_id, path_request: None });
+                }
+            },
+            Order::Stop => {
+                self.drop_actions(task_queue, path_requests);
+                self.action_stack.push(Action::Hold);
+            },
+            Order::Resume => self.resume(),
+        }
+    }
+
+    fn resume(&mut self) {
+        self.action_stack.retain(|action| if let Action::Hold = *action { false } else { true });
+    }
+
+    /// Stops following any paths, so they will be found again when needed.
+    fn forget_paths(&mut self, path_requests: &mut PathRequests) {
+        self.action_stack.retain(|action| match *action {
+            Action::WaitForPath { .. } | Action::OpenDoor { .. } => false,
+            _ => true,
+        });
+
+        for action in &mut self.action_stack {
+            match *action {
+                Action::FollowPath { ref mut replan, .. } => *replan = true,
+                Action::Work { ref mut path_request, .. } |
+                Action::MoveTo { ref mut path_request, .. } => {
+                    if let Some(request) = path_request.take() {
+                        path_requests.cancel(request);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Drops everything the unit is doing, giving back any tasks it was working on.
+    pub fn drop_actions(&mut self, task_queue: &mut TaskQueue, path_requests: &mut PathRequests) {
+        self.forget_paths(path_requests);
+
+        for action in &self.action_stack {
+            if let Action::Work { task_id, .. } = *action {
+                if let Some(task) = task_queue.get_mut(task_id) {
+                    task.assigned = false;
+                }
+            }
+        }
+
+        self.action_stack = vec!(Action::FindTask);
+    }
+
     pub fn update(
         &mut self, log: &Logger,
         object_classes: &ObjectClasses,
         tiles: &mut Tiles<Tile>, task_queue: &mut TaskQueue,
+        pathfinder: &Pathfinder, path_requests: &mut PathRequests,
+        reservations: &Reservations, unit_index: usize,
         delta: f32,
     ) -> Result<(), Error> {
+        let old_position = self.position;
         let result = {
             let action = self.action_stack.iter_mut().last().unwrap();
             action.update(
-                log, object_classes, tiles, task_queue, &mut self.position, delta,
+                log, object_classes, tiles, task_queue, pathfinder, path_requests,
+                reservations, unit_index, &self.crew, &mut self.position, delta,
             )?
         };
 
@@ -53,82 +292,257 @@ impl Unit {
             ActionResult::Push(action) => {
                 self.action_stack.push(action);
             },
+            ActionResult::GiveUp(reason) => {
+                info!(log, "Unit at {:?} gave up: {:?}", self.position, reason);
+                self.action_stack.pop();
+
+                // If there's no way to where we were ordered to go anymore, we can't follow the
+                // order either
+                if let GiveUpReason::PathBlocked(_) = reason {
+                    if let Some(&Action::MoveTo { .. }) = self.action_stack.iter().last() {
+                        self.action_stack.pop();
+                    }
+                }
+                self.last_give_up = Some(reason);
+            },
         }
 
+        self.update_activity(old_position, task_queue, delta);
+
         Ok(())
     }
+
+    /// Works out what the unit looks like it's doing, from what happened during an update.
+    fn update_activity(&mut self, old_position: Point2<f32>, task_queue: &TaskQueue, delta: f32) {
+        let movement = self.position - old_position;
+        let position = self.position;
+        let work_position = self.action_stack.iter().last()
+            .and_then(|action| if let Action::Work { task_id, .. } = *action {
+                task_queue.get(task_id).map(|task| task.position)
+            } else {
+                None
+            })
+            .and_then(|task_position| if is_next_to(position, task_position) {
+                Some(task_position)
+            } else {
+                None
+            });
+
+        let activity = if movement.x != 0.0 || movement.y != 0.0 {
+            self.facing = Facing::from_direction(movement.x, movement.y);
+            Activity::Walking
+        } else if let Some(task_position) = work_position {
+            self.facing = Facing::from_direction(
+                task_position.x as f32 + 0.5 - self.position.x,
+                task_position.y as f32 + 0.5 - self.position.y,
+            );
+            Activity::Working
+        } else {
+            Activity::Idle
+        };
+
+        if activity == self.activity {
+            self.activity_time += delta;
+        } else {
+            self.activity = activity;
+            self.activity_time = 0.0;
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
 enum Action {
     FindTask,
-    Work { task_id: TaskId },
-    FollowPath { path: Vec<Point2<i32>> },
+    Work { task_id: TaskId, path_request: Option<PathRequestId> },
+    MoveTo { target: Point2<i32>, path_request: Option<PathRequestId> },
+    Hold,
+    WaitForPath { request: PathRequestId },
+    FollowPath { path: Vec<Point2<i32>>, replan: bool, waited: f32 },
     OpenDoor { target: Point2<i32> },
 }
 
 impl Action {
+    fn describe(&self, task_queue: &TaskQueue, object_classes: &ObjectClasses) -> String {
+        match *self {
+            Action::FindTask => "Looking for work".into(),
+            Action::Work { task_id, .. } => {
+                if let Some(task) = task_queue.get(task_id) {
+                    format!(
+                        "{} at ({}, {})",
+                        task.describe(object_classes), task.position.x, task.position.y,
+                    )
+                } else {
+                    "Working on a removed task".into()
+                }
+            },
+            Action::MoveTo { target, .. } => format!("Moving to ({}, {})", target.x, target.y),
+            Action::Hold => "Holding position".into(),
+            Action::WaitForPath { .. } => "waiting for a path".into(),
+            Action::FollowPath { ref path, .. } =>
+                format!("following path, {} tiles left", path.len()),
+            Action::OpenDoor { target } =>
+                format!("opening door at ({}, {})", target.x, target.y),
+        }
+    }
+
     fn update(
         &mut self,
         log: &Logger,
         object_classes: &ObjectClasses,
         tiles: &mut Tiles<Tile>, task_queue: &mut TaskQueue,
-        unit_position: &mut Point2<f32>, delta: f32,
+        pathfinder: &Pathfinder, path_requests: &mut PathRequests,
+        reservations: &Reservations, unit_index: usize,
+        crew: &CrewMember, unit_position: &mut Point2<f32>, delta: f32,
     ) -> Result<ActionResult, Error> {
+        let unit_tile = Point2::new(unit_position.x as i32, unit_position.y as i32);
+
         let result = match *self {
             Action::FindTask => {
-                if let Some(task_id) = task_queue.assign(log, *unit_position) {
-                    ActionResult::Push(Action::Work { task_id })
+                if let Some(task_id) = task_queue.assign(
+                    log, *unit_position, crew, pathfinder.regions(),
+                ) {
+                    ActionResult::Push(Action::Work { task_id, path_request: None })
                 } else {
                     ActionResult::Continue
                 }
             },
-            Action::Work { task_id } => {
-                let task = task_queue.get_mut(task_id).unwrap();
-
-                let task_center = Point2::new(
-                    task.position.x as f32 + 0.5,
-                    task.position.y as f32 + 0.5
-                );
+            Action::Work { task_id, ref mut path_request } => {
+                let task_position = if let Some(task) = task_queue.get(task_id) {
+                    task.position
+                } else {
+                    // Someone removed the task while we were working on it
+                    if let Some(request) = path_request.take() {
+                        path_requests.cancel(request);
+                    }
+                    return Ok(ActionResult::GiveUp(GiveUpReason::TaskRemoved))
+                };
 
                 // Check if we're at the destination
-                if (task_center.x - unit_position.x).abs() < 1.1 &&
-                   (task_center.y - unit_position.y).abs() < 1.1 {
+                let at_task = is_next_to(*unit_position, task_position);
+
+                // If someone else is already working from this spot, spread out to a free spot
+                // that's also next to the task if there is one
+                if at_task && reservations.is_shared(unit_index, unit_tile) {
+                    if let Some(free) = find_free_spot(
+                        unit_tile, task_position, tiles, object_classes, task_queue,
+                        reservations, unit_index,
+                    )? {
+                        return Ok(ActionResult::Push(Action::FollowPath {
+                            path: vec!(free), replan: false, waited: 0.0,
+                        }))
+                    }
+                }
+
+                let task = task_queue.get_mut(task_id).unwrap();
+                if at_task {
+                    // If we were still waiting on a path we don't need it anymore
+                    if let Some(request) = path_request.take() {
+                        path_requests.cancel(request);
+                    }
+
                     // We're there, apply work
-                    task.apply_work(delta);
+                    task.apply_work(delta, &crew.skills);
 
-                    // If the work's done, we can add an object to the tile
+                    // If the work's done, we can change the tile
                     if task.is_done() {
-                        tiles.get_mut(task.position).unwrap()
-                            .object = Some(object_classes.create_object(
-                                task.object_class
-                            )?);
+                        let tile = tiles.get_mut(task.position).unwrap();
+                        match task.kind {
+                            TaskKind::Build(object_class) =>
+                                tile.object = Some(object_classes.create_object(object_class)?),
+                            TaskKind::Repair => {
+                                tile.damage = 0.0;
+                                if let Some(ref mut object) = tile.object {
+                                    object.damage = 0.0;
+                                }
+                            },
+                        }
                         tiles.changed.raise();
 
                         ActionResult::Done
                     } else {
                         ActionResult::Continue
                     }
-                } else {
-                    // We're not there, find a path to our destination
-                    if let Some(path) = pathfinding::find_path(
-                        Point2::new(unit_position.x as i32, unit_position.y as i32),
-                        task.position, false, 1.0 / UNIT_SPEED,
-                        tiles, object_classes,
-                    ) {
-                        ActionResult::Push(Action::FollowPath { path })
-                    } else {
-                        // We couldn't find a path, mark the task as unreachable
-                        task.unreachable = true;
-                        task.assigned = false;
+                } else if let Some(request) = path_request.take() {
+                    // We've asked for a path before, see what we got
+                    match path_requests.take(request) {
+                        Some(Some(path)) =>
+                            ActionResult::Push(Action::FollowPath {
+                                path, replan: false, waited: 0.0,
+                            }),
+                        Some(None) => {
+                            // We couldn't find a path, mark the task as unreachable
+                            task.unreachable = Some(UnreachableReason::NoPath);
+                            task.assigned = false;
 
-                        info!(log, "Unassigned task {}, it's unreachable", task_id.0);
+                            info!(log, "Unassigned task {}, it's unreachable", task_id.0);
 
-                        ActionResult::Continue
+                            ActionResult::GiveUp(GiveUpReason::Unreachable)
+                        },
+                        // The request doesn't exist anymore, we'll ask again next update
+                        None => ActionResult::Continue,
                     }
+                } else {
+                    // We're not there, ask for a path to our destination and wait for it
+                    let request = path_requests.request(PathRequest {
+                        start: unit_tile,
+                        goal: task.position,
+                        goal_inclusive: false,
+                        seconds_per_unit: 1.0 / UNIT_SPEED,
+                    });
+                    *path_request = Some(request);
+
+                    ActionResult::Push(Action::WaitForPath { request })
                 }
             },
-            Action::FollowPath { ref mut path } => {
+            Action::MoveTo { target, ref mut path_request } => {
+                if unit_tile == target {
+                    ActionResult::Done
+                } else if let Some(request) = path_request.take() {
+                    // We've asked for a path before, see what we got
+                    match path_requests.take(request) {
+                        Some(Some(path)) =>
+                            ActionResult::Push(Action::FollowPath {
+                                path, replan: false, waited: 0.0,
+                            }),
+                        Some(None) => ActionResult::GiveUp(GiveUpReason::Unreachable),
+                        // The request doesn't exist anymore, we'll ask again next update
+                        None => ActionResult::Continue,
+                    }
+                } else {
+                    let request = path_requests.request(PathRequest {
+                        start: unit_tile,
+                        goal: target,
+                        goal_inclusive: true,
+                        seconds_per_unit: 1.0 / UNIT_SPEED,
+                    });
+                    *path_request = Some(request);
+
+                    ActionResult::Push(Action::WaitForPath { request })
+                }
+            },
+            // Stay where we are until we're told otherwise
+            Action::Hold => ActionResult::Continue,
+            Action::WaitForPath { request } => {
+                if path_requests.is_pending(request) {
+                    ActionResult::Continue
+                } else {
+                    ActionResult::Done
+                }
+            },
+            Action::FollowPath { ref mut path, replan, ref mut waited } => {
+                // If the world changed along our path, stop following it so whatever we're
+                // following it for can find a new one
+                if replan {
+                    for node in path.iter().rev() {
+                        let walkable = Walkable::from_tile_res(tiles.get(*node), object_classes)?;
+                        if walkable == Walkable::Never {
+                            return Ok(path_blocked(*node, unit_tile, path, pathfinder))
+                        }
+                    }
+
+                    return Ok(ActionResult::Done)
+                }
+
                 let target = *path.iter().last().unwrap();
                 let target = Point2::new(target.x as f32 + 0.5, target.y as f32 + 0.5);
 
@@ -146,11 +560,25 @@ impl Action {
                         ActionResult::Done
                     } else {
                         let next_target = path[path.len() - 2];
+
+                        // Don't walk into other units, unless we're swapping places with them
+                        // or we've been waiting for too long
+                        if reservations.blocking(unit_index, next_target).is_some() {
+                            let swapping =
+                                reservations.is_swapping(unit_index, unit_tile, next_target);
+                            if !swapping && *waited < MAX_WAIT {
+                                *waited += delta;
+                                return Ok(ActionResult::Continue)
+                            }
+                        }
+                        *waited = 0.0;
+
                         let next_tile = tiles.get(next_target)?;
                         match next_tile.walkable(object_classes).unwrap() {
                             // If it's never walkable, something probably changed in the world that
-                            // now makes this blocked, just give up on following it
-                            Walkable::Never => ActionResult::Done,
+                            // now makes this blocked, stop following it
+                            Walkable::Never =>
+                                path_blocked(next_target, unit_tile, path, pathfinder),
                             // If it's always walkable, nothing to worry about, continue to the
                             // next path node
                             Walkable::Always => {
@@ -211,5 +639,229 @@ impl Action {
 enum ActionResult {
     Continue,
     Done,
-    Push(Action)
+    Push(Action),
+    GiveUp(GiveUpReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, Deserialize, Serialize)]
+pub struct UnitId(pub u32);
+
+/// Direct orders the player can give to units.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Order {
+    MoveTo(Point2<i32>),
+    /// Drop whatever the unit is doing and work on the task.
+    PrioritizeTask(TaskId),
+    /// Drop whatever the unit is doing and stay in place.
+    Stop,
+    /// Go back to doing things on its own after being stopped.
+    Resume,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub enum GiveUpReason {
+    /// The task being worked on was removed from the queue.
+    TaskRemoved,
+    /// No path could be found to the task being worked on.
+    Unreachable,
+    /// A tile on the path being followed can't be walked over anymore.
+    PathBlocked(Point2<i32>),
+}
+
+/// Which way a unit is looking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Facing {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// Finds the facing closest to a direction, preferring left and right for exact diagonals.
+    pub fn from_direction(x: f32, y: f32) -> Self {
+        if x.abs() >= y.abs() {
+            if x < 0.0 { Facing::Left } else { Facing::Right }
+        } else if y < 0.0 {
+            Facing::Down
+        } else {
+            Facing::Up
+        }
+    }
+}
+
+/// What a unit looks like it's doing, to be able to show it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Activity {
+    Idle,
+    Walking,
+    Working,
+}
+
+/// Checks if a unit at a position is close enough to a tile to work on it.
+fn is_next_to(position: Point2<f32>, tile: Point2<i32>) -> bool {
+    (tile.x as f32 + 0.5 - position.x).abs() < 1.1 &&
+        (tile.y as f32 + 0.5 - position.y).abs() < 1.1
+}
+
+/// Stops following a path that can't be walked anymore because of a blocked tile. If there's
+/// another way to where the path goes a new path can be found, otherwise there's no use trying.
+fn path_blocked(
+    blocked: Point2<i32>, unit_tile: Point2<i32>, path: &[Point2<i32>], pathfinder: &Pathfinder,
+) -> ActionResult {
+    if pathfinder.regions().can_reach(unit_tile, path[0], true) {
+        ActionResult::Done
+    } else {
+        ActionResult::GiveUp(GiveUpReason::PathBlocked(blocked))
+    }
+}
+
+/// Finds a tile next to the task that's free of other units and tasks, to work on the task from.
+fn find_free_spot(
+    unit_tile: Point2<i32>, task_position: Point2<i32>,
+    tiles: &Tiles<Tile>, object_classes: &ObjectClasses, task_queue: &TaskQueue,
+    reservations: &Reservations, unit_index: usize,
+) -> Result<Option<Point2<i32>>, Error> {
+    for y in -1..2 {
+        for x in -1..2 {
+            let spot = Point2::new(unit_tile.x + x, unit_tile.y + y);
+            let offset = spot - task_position;
+            if spot == unit_tile || spot == task_position ||
+               offset.x.abs() > 1 || offset.y.abs() > 1 {
+                continue
+            }
+
+            if Walkable::from_tile_res(tiles.get(spot), object_classes)? == Walkable::Always &&
+               task_queue.get_at(spot).is_none() &&
+               reservations.blocking(unit_index, spot).is_none() {
+                return Ok(Some(spot))
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        cgmath::{Point2, Vector2},
+
+        state::ship::{Order, GiveUpReason, Task},
+        test_utils::{self, WALL},
+    };
+
+    #[test]
+    fn prioritizing_takes_task_from_other_unit() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 7));
+        let position = Point2::new(2, 3);
+        ship.task_queue.queue(Task::new(position, WALL, &object_classes).unwrap()).unwrap();
+        let task_id = ship.task_queue.get_at(position).unwrap();
+
+        let near = test_utils::add_unit(&mut ship, Point2::new(1, 3));
+        test_utils::run(&mut ship, &object_classes, 1);
+        assert_eq!(ship.unit(near).unwrap().assigned_task(), Some(task_id));
+
+        let far = test_utils::add_unit(&mut ship, Point2::new(7, 3));
+        ship.give_order(&log, far, Order::PrioritizeTask(task_id)).unwrap();
+        test_utils::run(&mut ship, &object_classes, 1);
+
+        assert_eq!(ship.unit(near).unwrap().assigned_task(), None);
+        assert_eq!(ship.unit(far).unwrap().assigned_task(), Some(task_id));
+        assert!(ship.task_queue.get(task_id).unwrap().assigned);
+    }
+
+    #[test]
+    fn describes_walking_to_work() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 5));
+        ship.task_queue.queue(Task::new(Point2::new(7, 2), WALL, &object_classes).unwrap())
+            .unwrap();
+        let id = test_utils::add_unit(&mut ship, Point2::new(1, 2));
+        test_utils::run(&mut ship, &object_classes, 5);
+
+        let unit = ship.unit(id).unwrap();
+        let tiles_left = unit.path().unwrap().len();
+        assert_eq!(unit.describe_actions(&ship.task_queue, &object_classes), vec!(
+            "Build Wall at (7, 2)".to_string(),
+            format!("following path, {} tiles left", tiles_left),
+        ));
+        assert_eq!(
+            unit.describe(&ship.task_queue, &object_classes),
+            format!("Build Wall at (7, 2) - following path, {} tiles left", tiles_left),
+        );
+    }
+
+    #[test]
+    fn cancelling_stops_the_working_unit() {
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 7));
+        let position = Point2::new(7, 3);
+        ship.task_queue.queue(Task::new(position, WALL, &object_classes).unwrap()).unwrap();
+        let task_id = ship.task_queue.get_at(position).unwrap();
+
+        let id = test_utils::add_unit(&mut ship, Point2::new(1, 3));
+        test_utils::run(&mut ship, &object_classes, 5);
+        assert_eq!(ship.unit(id).unwrap().assigned_task(), Some(task_id));
+        assert!(ship.unit(id).unwrap().path().is_some());
+
+        ship.cancel_task(task_id).unwrap();
+        assert_eq!(ship.unit(id).unwrap().assigned_task(), None);
+        assert!(ship.unit(id).unwrap().path().is_none());
+        assert!(ship.task_queue.get(task_id).is_none());
+
+        // Cancelling it again, for example when it finished in the meantime, isn't a crash
+        assert!(ship.cancel_task(task_id).is_err());
+    }
+
+    #[test]
+    fn replans_around_changed_tiles() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(9, 7));
+        let (start, goal, wall) = (Point2::new(1, 3), Point2::new(7, 3), Point2::new(5, 3));
+
+        let id = test_utils::add_unit(&mut ship, start);
+        ship.give_order(&log, id, Order::MoveTo(goal)).unwrap();
+        test_utils::run(&mut ship, &object_classes, 5);
+        assert!(ship.unit(id).unwrap().path().unwrap().contains(&wall));
+
+        test_utils::place(&mut ship, &object_classes, wall, WALL);
+        for _ in 0..100 {
+            test_utils::run(&mut ship, &object_classes, 1);
+
+            let unit = ship.unit(id).unwrap();
+            assert_ne!(unit.tile_position(), wall);
+            if let Some(path) = unit.path() {
+                assert!(!path.contains(&wall));
+            }
+        }
+
+        let unit = ship.unit(id).unwrap();
+        assert_eq!(unit.tile_position(), goal);
+        assert_eq!(unit.last_give_up(), None);
+    }
+
+    #[test]
+    fn gives_up_when_path_gets_cut_off() {
+        let log = test_utils::logger();
+        let object_classes = test_utils::object_classes();
+        let mut ship = test_utils::hull_ship(Vector2::new(12, 3));
+        let wall = Point2::new(6, 1);
+
+        let id = test_utils::add_unit(&mut ship, Point2::new(1, 1));
+        ship.give_order(&log, id, Order::MoveTo(Point2::new(10, 1))).unwrap();
+        test_utils::run(&mut ship, &object_classes, 5);
+        assert!(ship.unit(id).unwrap().path().unwrap().contains(&wall));
+
+        test_utils::place(&mut ship, &object_classes, wall, WALL);
+        test_utils::run(&mut ship, &object_classes, 50);
+
+        let unit = ship.unit(id).unwrap();
+        assert!(unit.path().is_none());
+        assert!(unit.tile_position().x < wall.x);
+        assert_eq!(unit.last_give_up(), Some(GiveUpReason::PathBlocked(wall)));
+    }
 }
@@ -0,0 +1,138 @@
+#[derive(Deserialize, Serialize)]
+pub struct SpeedState {
+    speed: Speed,
+    /// The speed to go back to when unpausing.
+    resume_speed: Speed,
+    step_queued: bool,
+}
+
+impl SpeedState {
+    pub fn new() -> Self {
+        SpeedState {
+            speed: Speed::Normal,
+            resume_speed: Speed::Normal,
+            step_queued: false,
+        }
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        if speed != Speed::Paused {
+            self.resume_speed = speed;
+        }
+        self.speed = speed;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.speed == Speed::Paused {
+            self.speed = self.resume_speed;
+        } else {
+            self.speed = Speed::Paused;
+        }
+    }
+
+    /// Pauses the game if it isn't already, and runs a single tick on the next update.
+    pub fn queue_step(&mut self) {
+        self.set_speed(Speed::Paused);
+        self.step_queued = true;
+    }
+
+    /// Gets how many fixed ticks the game should run for this update.
+    pub fn take_ticks(&mut self) -> u32 {
+        if self.step_queued {
+            self.step_queued = false;
+            return 1
+        }
+
+        self.speed.ticks_per_update()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Speed {
+    Paused,
+    Normal,
+    Double,
+    Quadruple,
+}
+
+impl Speed {
+    pub fn all() -> &'static [Speed] {
+        &[Speed::Paused, Speed::Normal, Speed::Double, Speed::Quadruple]
+    }
+
+    /// How many fixed ticks run per update, faster speeds run more ticks rather than using a
+    /// larger delta so the simulation behaves the same at any speed.
+    pub fn ticks_per_update(self) -> u32 {
+        match self {
+            Speed::Paused => 0,
+            Speed::Normal => 1,
+            Speed::Double => 2,
+            Speed::Quadruple => 4,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Speed::Paused => "Paused",
+            Speed::Normal => "1x",
+            Speed::Double => "2x",
+            Speed::Quadruple => "4x",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        state::speed::{SpeedState, Speed},
+    };
+
+    #[test]
+    fn paused_runs_no_ticks() {
+        let mut state = SpeedState::new();
+        state.set_speed(Speed::Paused);
+        assert_eq!(state.take_ticks(), 0);
+    }
+
+    #[test]
+    fn step_runs_a_single_tick() {
+        let mut state = SpeedState::new();
+        state.set_speed(Speed::Double);
+        state.queue_step();
+        assert_eq!(state.speed(), Speed::Paused);
+        assert_eq!(state.take_ticks(), 1);
+        assert_eq!(state.take_ticks(), 0);
+    }
+
+    #[test]
+    fn unpausing_restores_previous_speed() {
+        let mut state = SpeedState::new();
+        state.set_speed(Speed::Quadruple);
+        state.toggle_pause();
+        assert_eq!(state.speed(), Speed::Paused);
+        state.toggle_pause();
+        assert_eq!(state.speed(), Speed::Quadruple);
+
+        // Stepping pauses too, and shouldn't lose the speed to go back to
+        state.queue_step();
+        state.take_ticks();
+        state.toggle_pause();
+        assert_eq!(state.speed(), Speed::Quadruple);
+    }
+
+    #[test]
+    fn faster_speeds_run_more_ticks() {
+        let mut state = SpeedState::new();
+        let normal = state.take_ticks();
+        assert_eq!(normal, 1);
+
+        state.set_speed(Speed::Double);
+        assert_eq!(state.take_ticks(), normal * 2);
+        state.set_speed(Speed::Quadruple);
+        assert_eq!(state.take_ticks(), normal * 4);
+    }
+}
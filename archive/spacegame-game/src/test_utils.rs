@@ -0,0 +1,94 @@
+//! Small ships and object classes for running the simulation in tests, without any rendering.
+
+use {
+    cgmath::{Point2, Vector2},
+    ggez::graphics::{Rect},
+    slog::{Logger, Discard},
+
+    object_class::{ObjectClasses, ObjectClass, ObjectClassId, DoorObjectBehavior, Extinguisher},
+    state::ship::{Ship, TileLayer, Unit, UnitId, CrewMember, Role},
+};
+
+pub const DELTA: f32 = 0.1;
+
+pub const WALL: ObjectClassId = ObjectClassId { id: 0 };
+pub const DOOR: ObjectClassId = ObjectClassId { id: 1 };
+pub const EXTINGUISHER: ObjectClassId = ObjectClassId { id: 2 };
+
+pub fn logger() -> Logger {
+    Logger::root(Discard, o!())
+}
+
+/// Walls, doors and extinguishers, registered in the same order as their ids.
+pub fn object_classes() -> ObjectClasses {
+    let mut object_classes = ObjectClasses::new();
+    object_classes.register(class("Wall", None));
+    object_classes.register(ObjectClass {
+        behavior: Some(Box::new(DoorObjectBehavior)),
+        .. class("Door", None)
+    });
+    object_classes.register(class(
+        "Extinguisher", Some(Extinguisher { radius: 3.0, strength: 0.5 }),
+    ));
+    object_classes
+}
+
+/// A class with no behavior or special sprites, to build other test classes from.
+pub fn class(name: &str, extinguisher: Option<Extinguisher>) -> ObjectClass {
+    ObjectClass {
+        friendly_name: name.into(),
+        uvs: Rect::new(0.0, 0.0, 0.125, 0.125),
+        frames: Vec::new(),
+        autotile: None,
+        extinguisher,
+        build_work: 1.0,
+        build_requirement: None,
+        max_health: 60.0,
+        behavior: None,
+    }
+}
+
+/// Creates a ship of the given size, with floor everywhere but the outer ring of hull.
+pub fn hull_ship(size: Vector2<i32>) -> Ship {
+    let mut ship = Ship::empty(size);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let edge = x == 0 || y == 0 || x == size.x - 1 || y == size.y - 1;
+            ship.tiles.get_mut(Point2::new(x, y)).unwrap().layer =
+                if edge { TileLayer::Hull } else { TileLayer::Floor };
+        }
+    }
+    ship.tiles.changed.raise();
+
+    ship
+}
+
+pub fn place(
+    ship: &mut Ship, object_classes: &ObjectClasses, position: Point2<i32>, class: ObjectClassId,
+) {
+    let object = object_classes.create_object(class).unwrap();
+    ship.tiles.get_mut(position).unwrap().object = Some(object);
+    ship.tiles.changed.raise();
+}
+
+/// Adds an engineer standing in the middle of the tile. They can't go on EVA, so tests show what
+/// any crew member can do.
+pub fn add_unit(ship: &mut Ship, position: Point2<i32>) -> UnitId {
+    let id = add_crew(ship, position, Role::Engineer);
+    ship.unit_mut(id).unwrap().crew_mut().skills.eva = 0;
+    id
+}
+
+pub fn add_crew(ship: &mut Ship, position: Point2<i32>, role: Role) -> UnitId {
+    ship.add_unit(Unit::new(
+        Point2::new(position.x as f32 + 0.5, position.y as f32 + 0.5),
+        CrewMember::new("Test Crew".into(), role),
+    ))
+}
+
+pub fn run(ship: &mut Ship, object_classes: &ObjectClasses, ticks: usize) {
+    let log = logger();
+    for _ in 0..ticks {
+        ship.update(&log, object_classes, DELTA).unwrap();
+    }
+}
@@ -7,8 +7,9 @@ use {
     rivr::input::{PcInputHandler},
 
     spacegame_game::{
+        object_class::{ObjectClasses},
         state::{
-            ship::{Ship, Task},
+            ship::{Ship, Task, TileLayer},
             normalize_area, BuildState, BuildDrag, BuildChoice, Camera,
         },
     },
@@ -62,14 +63,15 @@ impl BuildInputHandler {
     }
 
     pub fn handle_mouse_up(
-        &mut self, button: MouseButton, state: &mut BuildState, ship: &mut Ship
+        &mut self, button: MouseButton, state: &mut BuildState,
+        object_classes: &ObjectClasses, ship: &mut Ship,
     ) -> GameResult<()> {
         if state.choice == BuildChoice::None {
             return Ok(())
         }
 
         match button {
-            MouseButton::Left => self.handle_build_up(state, ship),
+            MouseButton::Left => self.handle_build_up(state, object_classes, ship),
             MouseButton::Right => self.handle_cancel_up(state)?,
             _ => {},
         }
@@ -77,7 +79,9 @@ impl BuildInputHandler {
         Ok(())
     }
 
-    fn handle_build_up(&mut self, state: &mut BuildState, ship: &mut Ship) {
+    fn handle_build_up(
+        &mut self, state: &mut BuildState, object_classes: &ObjectClasses, ship: &mut Ship,
+    ) {
         // If we were currently dragging, switch back to hovering
         if let BuildDrag::Dragging { start, end } = state.drag {
             let mut world_changed = false;
@@ -92,20 +96,33 @@ impl BuildInputHandler {
                         BuildChoice::Floor => {
                             let tile = ship.tiles.get_mut(tile_pos).unwrap();
 
-                            if !tile.floor {
-                                tile.floor = true;
+                            if tile.layer != TileLayer::Floor {
+                                tile.layer = TileLayer::Floor;
+                                tile.damage = 0.0;
+                                world_changed = true;
+                                self.build_sound_queued = true;
+                            }
+                        },
+                        BuildChoice::Hull => {
+                            let has_task = ship.task_queue.get_at(tile_pos).is_some();
+                            let tile = ship.tiles.get_mut(tile_pos).unwrap();
+
+                            // Hull can't go under objects, those need floor
+                            if tile.layer != TileLayer::Hull && tile.object.is_none() && !has_task {
+                                tile.layer = TileLayer::Hull;
+                                tile.damage = 0.0;
                                 world_changed = true;
                                 self.build_sound_queued = true;
                             }
                         },
                         BuildChoice::Object(id) => {
                             let tile = ship.tiles.get_mut(tile_pos).unwrap();
-                            let has_tile = tile.floor;
+                            let has_tile = tile.layer == TileLayer::Floor;
                             let has_object = tile.object.is_some();
                             let has_task = ship.task_queue.get_at(tile_pos).is_some();
 
                             if has_tile && !has_object && !has_task {
-                                let task = Task::new(tile_pos, id, 1.0);
+                                let task = Task::new(tile_pos, id, object_classes).unwrap();
                                 ship.task_queue.queue(task).unwrap();
                                 self.build_sound_queued = true;
                             }
@@ -128,12 +145,13 @@ impl BuildInputHandler {
                         BuildChoice::DestroyAll => {
                             let tile = ship.tiles.get_mut(tile_pos).unwrap();
 
-                            if tile.floor || tile.object.is_some() {
+                            if tile.layer != TileLayer::Space || tile.object.is_some() {
                                 world_changed = true;
                                 self.build_sound_queued = true;
                             }
 
-                            tile.floor = false;
+                            tile.layer = TileLayer::Space;
+                            tile.damage = 0.0;
                             tile.object = None;
 
                             if let Some(task_id) = ship.task_queue.get_at(tile_pos) {
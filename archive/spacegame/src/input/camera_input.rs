@@ -1,19 +1,75 @@
-use ggez::event::{MouseButton};
-use nalgebra::{Vector2};
+use ggez::event::{MouseButton, Keycode};
+use nalgebra::{Point2, Vector2};
 
-use spacegame_game::state::{Camera};
+use spacegame_game::state::{Camera, ship::{Ship}};
+
+/// How much one step of the mouse wheel or a zoom key press zooms in or out.
+const ZOOM_STEP: f32 = 1.2;
+/// Panning speed when starting to pan, in screen pixels per second so it feels the same at any
+/// zoom level.
+const MIN_PAN_SPEED: f32 = 300.0;
+/// Panning speed after holding for a while, in screen pixels per second.
+const MAX_PAN_SPEED: f32 = 1200.0;
+/// How fast panning speeds up while held, in screen pixels per second per second.
+const PAN_ACCELERATION: f32 = 1200.0;
+/// How quickly the camera catches up with the panning speed it should have, higher is snappier.
+const PAN_SMOOTHING: f32 = 12.0;
+/// How close to the edge of the screen the cursor has to be to scroll, in pixels.
+const EDGE_SCROLL_MARGIN: i32 = 8;
 
 pub struct CameraInputHandler {
     move_down: bool,
+    last_mouse_position: Option<Point2<i32>>,
+
+    pan_keys: PanKeys,
+    pan_time: f32,
+    velocity: Vector2<f32>,
+    edge_scrolling: bool,
+}
+
+#[derive(Default)]
+struct PanKeys {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
 }
 
 impl CameraInputHandler {
     pub fn new() -> Self {
         CameraInputHandler {
             move_down: false,
+            last_mouse_position: None,
+
+            pan_keys: PanKeys::default(),
+            pan_time: 0.0,
+            velocity: Vector2::new(0.0, 0.0),
+            edge_scrolling: false,
         }
     }
 
+    pub fn update(&mut self, delta: f32, ship: &Ship, camera: &mut Camera) {
+        let direction = self.pan_direction(camera);
+
+        // Panning speeds up the longer it's held, but the camera eases into that speed
+        let target_velocity = if direction != Vector2::new(0.0, 0.0) {
+            self.pan_time += delta;
+            let speed = (MIN_PAN_SPEED + PAN_ACCELERATION * self.pan_time).min(MAX_PAN_SPEED);
+            direction.normalize() * (speed / camera.pixels_per_tile())
+        } else {
+            self.pan_time = 0.0;
+            Vector2::new(0.0, 0.0)
+        };
+        self.velocity += (target_velocity - self.velocity) * (PAN_SMOOTHING * delta).min(1.0);
+
+        let new_position = camera.position() + self.velocity * delta;
+        camera.set_position(new_position);
+
+        // Keep the ship in view, whatever moved the camera
+        let size = ship.tiles.size();
+        camera.clamp_to(Point2::new(0.0, 0.0), Point2::new(size.x as f32, size.y as f32));
+    }
+
     pub fn handle_mouse_down(&mut self, button: MouseButton) {
         if button != MouseButton::Middle {
             return
@@ -30,7 +86,38 @@ impl CameraInputHandler {
         self.move_down = false;
     }
 
-    pub fn handle_mouse_move(&mut self, mouse_move: Vector2<i32>, camera: &mut Camera) {
+    pub fn handle_mouse_wheel(&mut self, steps: i32, over_ui: bool, camera: &mut Camera) {
+        if over_ui {
+            return
+        }
+
+        // Zoom around the cursor so whatever is under it stays under it
+        let factor = ZOOM_STEP.powi(steps);
+        if let Some(position) = self.last_mouse_position {
+            camera.zoom_around(factor, position);
+        } else {
+            camera.zoom(factor);
+        }
+    }
+
+    pub fn handle_key_down(&mut self, keycode: Keycode, camera: &mut Camera) {
+        match keycode {
+            Keycode::Equals | Keycode::KpPlus | Keycode::PageUp => camera.zoom(ZOOM_STEP),
+            Keycode::Minus | Keycode::KpMinus | Keycode::PageDown => camera.zoom(1.0 / ZOOM_STEP),
+            Keycode::F2 => self.edge_scrolling = !self.edge_scrolling,
+            _ => self.set_pan_key(keycode, true),
+        }
+    }
+
+    pub fn handle_key_up(&mut self, keycode: Keycode) {
+        self.set_pan_key(keycode, false);
+    }
+
+    pub fn handle_mouse_move(
+        &mut self, mouse_position: Point2<i32>, mouse_move: Vector2<i32>, camera: &mut Camera,
+    ) {
+        self.last_mouse_position = Some(mouse_position);
+
         // If the move button is held down, we need to move the camera
         if self.move_down {
             // Use the relative position the mouse is moved, then scale it to how much that is
@@ -38,10 +125,51 @@ impl CameraInputHandler {
             let pixels_per_tile = camera.pixels_per_tile();
             let new_position = camera.position()
                 + Vector2::new(
-                    -mouse_move.x as f32 / pixels_per_tile as f32,
-                    mouse_move.y as f32 / pixels_per_tile as f32
+                    -mouse_move.x as f32 / pixels_per_tile,
+                    mouse_move.y as f32 / pixels_per_tile
                 );
             camera.set_position(new_position);
         }
     }
+
+    fn set_pan_key(&mut self, keycode: Keycode, held: bool) {
+        match keycode {
+            Keycode::W | Keycode::Up => self.pan_keys.up = held,
+            Keycode::S | Keycode::Down => self.pan_keys.down = held,
+            Keycode::A | Keycode::Left => self.pan_keys.left = held,
+            Keycode::D | Keycode::Right => self.pan_keys.right = held,
+            _ => {},
+        }
+    }
+
+    /// Finds the direction the player wants to pan in, in world space where up is positive.
+    fn pan_direction(&self, camera: &Camera) -> Vector2<f32> {
+        let mut up = self.pan_keys.up;
+        let mut down = self.pan_keys.down;
+        let mut left = self.pan_keys.left;
+        let mut right = self.pan_keys.right;
+
+        if self.edge_scrolling && !self.move_down {
+            if let Some(position) = self.last_mouse_position {
+                let screen_size = camera.screen_size();
+                up |= position.y < EDGE_SCROLL_MARGIN;
+                down |= position.y >= screen_size.y - EDGE_SCROLL_MARGIN;
+                left |= position.x < EDGE_SCROLL_MARGIN;
+                right |= position.x >= screen_size.x - EDGE_SCROLL_MARGIN;
+            }
+        }
+
+        Vector2::new(
+            axis(left, right),
+            axis(down, up),
+        )
+    }
+}
+
+fn axis(negative: bool, positive: bool) -> f32 {
+    match (negative, positive) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    }
 }
@@ -0,0 +1,21 @@
+use ggez::event::{Keycode};
+
+use spacegame_game::state::{GameState};
+
+pub fn handle_key_down(keycode: Keycode, game_state: &mut GameState) {
+    let text = match keycode {
+        Keycode::F5 => {
+            let director = &mut game_state.director;
+            director.frequency = director.frequency.cycle();
+            format!("Event frequency: {}", director.frequency.describe())
+        },
+        Keycode::F6 => {
+            let director = &mut game_state.director;
+            director.difficulty = director.difficulty.cycle();
+            format!("Event difficulty: {}", director.difficulty.describe())
+        },
+        _ => return,
+    };
+
+    game_state.notifications.push(text);
+}
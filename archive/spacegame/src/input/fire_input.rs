@@ -0,0 +1,17 @@
+use ggez::event::{Keycode};
+
+use spacegame_game::state::{GameState, BuildDrag};
+
+/// How intense fires started by hand are.
+const DEBUG_FIRE_INTENSITY: f32 = 0.5;
+
+/// Debug hotkey to set the hovered tile on fire, to see how fires behave.
+pub fn handle_key_down(keycode: Keycode, game_state: &mut GameState) {
+    if keycode != Keycode::F4 {
+        return
+    }
+
+    if let BuildDrag::Hovering { position: Some(position) } = game_state.build_state.drag {
+        game_state.ship.fires.ignite(position, DEBUG_FIRE_INTENSITY);
+    }
+}
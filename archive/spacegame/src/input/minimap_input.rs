@@ -0,0 +1,58 @@
+use ggez::event::{MouseButton};
+use nalgebra::{Point2};
+
+use {
+    spacegame_game::state::{Camera, ship::{Ship}},
+    minimap::{MinimapLayout},
+};
+
+pub struct MinimapInputHandler {
+    dragging: bool,
+}
+
+impl MinimapInputHandler {
+    pub fn new() -> Self {
+        MinimapInputHandler {
+            dragging: false,
+        }
+    }
+
+    /// Starts moving the camera if the minimap is clicked, returns if the minimap took the click.
+    pub fn handle_mouse_down(
+        &mut self, button: MouseButton, mouse_position: Point2<i32>,
+        camera: &mut Camera, ship: &Ship,
+    ) -> bool {
+        let layout = MinimapLayout::new(camera.screen_size(), ship);
+        if button != MouseButton::Left || !layout.contains(mouse_position) {
+            return false
+        }
+
+        self.dragging = true;
+        jump_to(mouse_position, camera, ship);
+        true
+    }
+
+    /// Stops moving the camera, returns if the minimap had taken the click.
+    pub fn handle_mouse_up(&mut self, button: MouseButton) -> bool {
+        if button != MouseButton::Left || !self.dragging {
+            return false
+        }
+
+        self.dragging = false;
+        true
+    }
+
+    pub fn handle_mouse_move(
+        &mut self, mouse_position: Point2<i32>, camera: &mut Camera, ship: &Ship,
+    ) {
+        // Keep following the cursor while held, so the minimap can be dragged over
+        if self.dragging {
+            jump_to(mouse_position, camera, ship);
+        }
+    }
+}
+
+fn jump_to(mouse_position: Point2<i32>, camera: &mut Camera, ship: &Ship) {
+    let layout = MinimapLayout::new(camera.screen_size(), ship);
+    camera.set_position(layout.screen_to_world(mouse_position));
+}
@@ -1,21 +1,31 @@
 mod build_input;
 mod camera_input;
+mod director_input;
+mod fire_input;
+mod minimap_input;
+mod overlay_input;
+mod selection_input;
+mod speed_input;
 
 use {
     ggez::{
         Context, GameResult,
-        event::{MouseButton},
+        event::{MouseButton, Keycode},
     },
     nalgebra::{Point2, Vector2},
+    slog::{Logger},
 
     rivr::input::{PcInputHandler},
 
     spacegame_game::{
+        object_class::{ObjectClasses},
         state::{GameState},
     },
     input::{
         build_input::{BuildInputHandler},
         camera_input::{CameraInputHandler},
+        minimap_input::{MinimapInputHandler},
+        selection_input::{SelectionInputHandler},
     },
     ui::{UiSystem},
 };
@@ -23,6 +33,8 @@ use {
 pub struct InputHandler {
     build_input: BuildInputHandler,
     camera_input: CameraInputHandler,
+    minimap_input: MinimapInputHandler,
+    selection_input: SelectionInputHandler,
     ui_input: PcInputHandler,
 }
 
@@ -34,12 +46,15 @@ impl InputHandler {
         Ok(InputHandler {
             build_input,
             camera_input,
+            minimap_input: MinimapInputHandler::new(),
+            selection_input: SelectionInputHandler::new(),
             ui_input: PcInputHandler::new(),
         })
     }
 
-    pub fn update(&mut self) -> GameResult<()> {
+    pub fn update(&mut self, delta: f32, game_state: &mut GameState) -> GameResult<()> {
         self.build_input.update()?;
+        self.camera_input.update(delta, &game_state.ship, &mut game_state.camera);
 
         Ok(())
     }
@@ -49,31 +64,72 @@ impl InputHandler {
         button: MouseButton, x: i32, y: i32,
         ui_system: &mut UiSystem, game_state: &mut GameState,
     ) {
+        // The minimap is drawn over everything else, so it gets the first chance at clicks
+        let taken = self.minimap_input.handle_mouse_down(
+            button, Point2::new(x, y), &mut game_state.camera, &game_state.ship,
+        );
+        if taken {
+            return
+        }
+
         let position_f = Point2::new(x as f32, y as f32);
 
         self.ui_input.handle_drag_started(
             position_f, &mut ui_system.ui, &ui_system.frame
         ).unwrap();
         self.build_input.handle_mouse_down(button, &mut game_state.build_state);
+        self.selection_input.handle_mouse_down(
+            button, &game_state.build_state, &mut game_state.selection,
+        );
         self.camera_input.handle_mouse_down(button);
     }
 
     pub fn handle_button_up(
-        &mut self,
+        &mut self, log: &Logger,
         button: MouseButton, x: i32, y: i32,
-        ui_system: &mut UiSystem, game_state: &mut GameState,
+        ui_system: &mut UiSystem, object_classes: &ObjectClasses, game_state: &mut GameState,
     ) {
+        if self.minimap_input.handle_mouse_up(button) {
+            return
+        }
+
         let position_f = Point2::new(x as f32, y as f32);
 
         self.ui_input.handle_drag_ended(
             position_f, &mut ui_system.ui, &ui_system.frame
         ).unwrap();
+        // Selection goes first, so releasing the mouse after cancelling a build doesn't also
+        // give orders
+        self.selection_input.handle_mouse_up(
+            log, button, &game_state.build_state, &mut game_state.selection, &mut game_state.ship,
+        );
         self.build_input.handle_mouse_up(
-            button, &mut game_state.build_state, &mut game_state.ship
+            button, &mut game_state.build_state, object_classes, &mut game_state.ship
         ).unwrap();
         self.camera_input.handle_mouse_up(button);
     }
 
+    pub fn handle_key_down(&mut self, log: &Logger, keycode: Keycode, game_state: &mut GameState) {
+        self.selection_input.handle_key_down(
+            log, keycode, &mut game_state.selection, &mut game_state.ship,
+        );
+        self.camera_input.handle_key_down(keycode, &mut game_state.camera);
+        speed_input::handle_key_down(keycode, &mut game_state.speed);
+        overlay_input::handle_key_down(keycode, game_state);
+        director_input::handle_key_down(keycode, game_state);
+        fire_input::handle_key_down(keycode, game_state);
+    }
+
+    pub fn handle_key_up(&mut self, keycode: Keycode) {
+        self.camera_input.handle_key_up(keycode);
+    }
+
+    pub fn handle_wheel(&mut self, y: i32, game_state: &mut GameState) {
+        self.camera_input.handle_mouse_wheel(
+            y, self.ui_input.is_cursor_over_ui(), &mut game_state.camera,
+        );
+    }
+
     pub fn handle_motion(
         &mut self,
         x: i32, y: i32, xrel: i32, yrel: i32,
@@ -90,6 +146,12 @@ impl InputHandler {
             position, &self.ui_input,
             &mut game_state.build_state, &mut game_state.camera, &mut game_state.ship
         );
-        self.camera_input.handle_mouse_move(rel_position, &mut game_state.camera);
+        self.selection_input.handle_mouse_move(
+            position, &self.ui_input, &mut game_state.selection, &game_state.camera,
+        );
+        self.camera_input.handle_mouse_move(position, rel_position, &mut game_state.camera);
+        self.minimap_input.handle_mouse_move(
+            position, &mut game_state.camera, &game_state.ship,
+        );
     }
 }
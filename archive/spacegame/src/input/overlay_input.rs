@@ -0,0 +1,11 @@
+use ggez::event::{Keycode};
+
+use spacegame_game::state::{GameState, ship::{Overlay}};
+
+pub fn handle_key_down(keycode: Keycode, game_state: &mut GameState) {
+    match keycode {
+        Keycode::O => game_state.overlay = Overlay::cycle(game_state.overlay),
+        Keycode::F3 => game_state.path_debug = !game_state.path_debug,
+        _ => {},
+    }
+}
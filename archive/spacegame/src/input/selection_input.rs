@@ -0,0 +1,141 @@
+use ggez::event::{MouseButton, Keycode};
+use nalgebra::{Point2};
+use slog::{Logger};
+
+use {
+    rivr::input::{PcInputHandler},
+
+    spacegame_game::{
+        state::{
+            ship::{Ship, Order},
+            BuildState, BuildChoice, SelectionState, SelectionDrag, Camera,
+        },
+    },
+};
+
+/// How far away from a unit's center a click still selects it, in tiles.
+const CLICK_DISTANCE: f32 = 0.5;
+/// How far the cursor has to move, in tiles, before a click becomes a box selection.
+const DRAG_DISTANCE: f32 = 0.2;
+
+pub struct SelectionInputHandler {
+    last_world_position: Option<Point2<f32>>,
+}
+
+impl SelectionInputHandler {
+    pub fn new() -> Self {
+        SelectionInputHandler {
+            last_world_position: None,
+        }
+    }
+
+    pub fn handle_mouse_down(
+        &mut self, button: MouseButton, build_state: &BuildState, state: &mut SelectionState,
+    ) {
+        // Building takes over the mouse
+        if button != MouseButton::Left || build_state.choice != BuildChoice::None {
+            return
+        }
+
+        if let Some(position) = self.last_world_position {
+            state.drag = SelectionDrag::Dragging { start: position, end: position };
+        }
+    }
+
+    pub fn handle_mouse_up(
+        &mut self, log: &Logger, button: MouseButton,
+        build_state: &BuildState, state: &mut SelectionState, ship: &mut Ship,
+    ) {
+        if build_state.choice != BuildChoice::None {
+            state.drag = SelectionDrag::None;
+            return
+        }
+
+        match button {
+            MouseButton::Left => self.handle_select_up(state, ship),
+            MouseButton::Right => self.handle_order_up(log, state, ship),
+            _ => {},
+        }
+    }
+
+    pub fn handle_key_down(
+        &mut self, log: &Logger, keycode: Keycode, state: &mut SelectionState, ship: &mut Ship,
+    ) {
+        match keycode {
+            Keycode::H => give_orders(log, state, ship, Order::Stop),
+            Keycode::R => give_orders(log, state, ship, Order::Resume),
+            Keycode::Escape => state.selected.clear(),
+            _ => {},
+        }
+    }
+
+    pub fn handle_mouse_move(
+        &mut self,
+        mouse_position: Point2<i32>, ui_input: &PcInputHandler,
+        state: &mut SelectionState, camera: &Camera,
+    ) {
+        let world_position = camera.screen_to_world(mouse_position);
+
+        // Dragging continues even over UI, but new selections can't start on it
+        if let SelectionDrag::Dragging { ref mut end, .. } = state.drag {
+            *end = world_position;
+        }
+
+        self.last_world_position = if !ui_input.is_cursor_over_ui() {
+            Some(world_position)
+        } else {
+            None
+        };
+    }
+
+    fn handle_select_up(&mut self, state: &mut SelectionState, ship: &Ship) {
+        let (start, end) = if let SelectionDrag::Dragging { start, end } = state.drag {
+            (start, end)
+        } else {
+            return
+        };
+        state.drag = SelectionDrag::None;
+
+        // A small drag is still just a click on a single unit
+        state.selected = if (end.x - start.x).abs() < DRAG_DISTANCE &&
+                            (end.y - start.y).abs() < DRAG_DISTANCE {
+            ship.unit_at(end, CLICK_DISTANCE).into_iter().collect()
+        } else {
+            ship.units_in_area(start, end)
+        };
+    }
+
+    fn handle_order_up(&mut self, log: &Logger, state: &mut SelectionState, ship: &mut Ship) {
+        let world_position = if let Some(position) = self.last_world_position {
+            position
+        } else {
+            return
+        };
+        let tile_position = Point2::new(
+            world_position.x.floor() as i32,
+            world_position.y.floor() as i32,
+        );
+
+        if !ship.tiles.is_in_bounds(tile_position) {
+            return
+        }
+
+        // Clicking on a task means working on it, anywhere else means moving there
+        let order = if let Some(task_id) = ship.task_queue.get_at(tile_position) {
+            Order::PrioritizeTask(task_id)
+        } else {
+            Order::MoveTo(tile_position)
+        };
+
+        give_orders(log, state, ship, order);
+    }
+}
+
+fn give_orders(log: &Logger, state: &mut SelectionState, ship: &mut Ship, order: Order) {
+    // Units may have gone away since they were selected
+    state.selected.retain(|id| ship.unit(*id).is_some());
+
+    for id in &state.selected {
+        ship.give_order(log, *id, order).unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+use ggez::event::{Keycode};
+
+use spacegame_game::state::{SpeedState, Speed};
+
+pub fn handle_key_down(keycode: Keycode, state: &mut SpeedState) {
+    match keycode {
+        Keycode::Space => state.toggle_pause(),
+        Keycode::Num1 => state.set_speed(Speed::Normal),
+        Keycode::Num2 => state.set_speed(Speed::Double),
+        Keycode::Num3 => state.set_speed(Speed::Quadruple),
+        Keycode::Period => state.queue_step(),
+        _ => {},
+    }
+}
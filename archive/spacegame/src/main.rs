@@ -9,12 +9,15 @@ extern crate lagato_ggez;
 extern crate spacegame_game;
 
 mod input;
+mod minimap;
 mod rendering;
 mod ui;
 
 use {
+    std::time::{Instant},
+
     ggez::{
-        event::{EventHandler, MouseButton, MouseState},
+        event::{EventHandler, MouseButton, MouseState, Keycode, Mod},
         graphics::{Rect},
         timer,
         Context, GameResult,
@@ -22,14 +25,20 @@ use {
     slog::{Logger},
 
     spacegame_game::{
-        object_class::{ObjectClasses, ObjectClass, DoorObjectBehavior},
-        state::{GameState},
+        object_class::{
+            ObjectClasses, ObjectClass, SkillRequirement, Autotile, AutotileNeighbors,
+            Extinguisher, DoorObjectBehavior, WalkwayObjectBehavior,
+        },
+        state::{GameState, ship::{Skill}},
     },
     input::{InputHandler},
     rendering::{Renderer},
     ui::{UiSystem},
 };
 
+const DESIRED_FPS: u32 = 60;
+const DELTA: f32 = 1.0 / DESIRED_FPS as f32;
+
 pub fn main() -> GameResult<()> {
     lagato_ggez::run_game(
         "spacegame", "carbidegames", "Space Game",
@@ -45,6 +54,8 @@ struct MainState {
 
     object_classes: ObjectClasses,
     game_state: GameState,
+    /// When the game was last updated, to know how far along the next update drawing is.
+    last_update: Instant,
 }
 
 impl MainState {
@@ -55,14 +66,66 @@ impl MainState {
         let mut object_classes = ObjectClasses::new();
         object_classes.register(ObjectClass {
             friendly_name: "Wall".into(),
-            uvs: Rect::new(0.0, 0.0, 0.5, 0.5),
+            uvs: Rect::new(0.0, 0.0, 0.125, 0.125),
+            frames: Vec::new(),
+            // Wall pieces for the first eight neighbor combinations are on the bottom row of the
+            // sheet, the rest are in the right half of the second and third rows
+            autotile: Some(Autotile {
+                neighbors: AutotileNeighbors::Straight,
+                sprites: (0..16)
+                    .map(|mask| if mask < 8 {
+                        Rect::new(mask as f32 * 0.125, 0.875, 0.125, 0.125)
+                    } else {
+                        let index = mask - 8;
+                        Rect::new(
+                            (4 + index % 4) as f32 * 0.125, (1 + index / 4) as f32 * 0.125,
+                            0.125, 0.125,
+                        )
+                    })
+                    .collect(),
+            }),
+            extinguisher: None,
+            build_work: 2.0,
+            build_requirement: None,
+            max_health: 100.0,
             behavior: None,
         });
         object_classes.register(ObjectClass {
             friendly_name: "Door".into(),
-            uvs: Rect::new(0.5, 0.0, 0.5, 0.5),
+            uvs: Rect::new(0.125, 0.0, 0.125, 0.125),
+            // Sliding open from closed to fully open
+            frames: (0..4)
+                .map(|i| Rect::new(i as f32 * 0.125, 0.25, 0.125, 0.125))
+                .collect(),
+            autotile: None,
+            extinguisher: None,
+            build_work: 4.0,
+            build_requirement: Some(SkillRequirement { skill: Skill::Construction, level: 3 }),
+            max_health: 60.0,
             behavior: Some(Box::new(DoorObjectBehavior)),
         });
+        object_classes.register(ObjectClass {
+            friendly_name: "Corridor".into(),
+            uvs: Rect::new(0.25, 0.0, 0.125, 0.125),
+            frames: Vec::new(),
+            autotile: None,
+            extinguisher: None,
+            build_work: 1.0,
+            build_requirement: None,
+            max_health: 40.0,
+            behavior: Some(Box::new(WalkwayObjectBehavior { walk_multiplier: 0.75 })),
+        });
+        object_classes.register(ObjectClass {
+            friendly_name: "Extinguisher".into(),
+            uvs: Rect::new(0.625, 0.0, 0.125, 0.125),
+            frames: Vec::new(),
+            autotile: None,
+            extinguisher: Some(Extinguisher { radius: 3.0, strength: 0.5 }),
+            build_work: 3.0,
+            build_requirement: None,
+            max_health: 40.0,
+            behavior: None,
+        });
 
         // Initialize game subsystems
         let renderer = Renderer::new(ctx)?;
@@ -79,27 +142,33 @@ impl MainState {
 
             object_classes,
             game_state,
+            last_update: Instant::now(),
         })
     }
 }
 
 impl EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        const DESIRED_FPS: u32 = 60;
-        const DELTA: f32 = 1.0 / DESIRED_FPS as f32;
-
         while timer::check_update_time(ctx, DESIRED_FPS) {
-            self.ui_system.update(&self.log, ctx, &mut self.game_state)?;
-            self.input_handler.update()?;
+            self.ui_system.update(
+                &self.log, ctx, &self.object_classes, &mut self.game_state,
+            )?;
+            self.input_handler.update(DELTA, &mut self.game_state)?;
             self.game_state.update(&self.log, &self.object_classes, DELTA).unwrap();
+            self.last_update = Instant::now();
         }
 
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // Units are drawn between where they were and where they are, so movement looks smooth
+        // even if drawing happens faster than the game updates
+        let elapsed = timer::duration_to_f64(self.last_update.elapsed()) as f32;
+        let interpolation = (elapsed / DELTA).min(1.0);
+
         self.renderer.render_frame(
-            ctx, &mut self.ui_system, &self.object_classes, &mut self.game_state
+            ctx, &mut self.ui_system, &self.object_classes, &mut self.game_state, interpolation,
         )
     }
 
@@ -117,10 +186,29 @@ impl EventHandler for MainState {
         button: MouseButton, x: i32, y: i32
     ) {
         self.input_handler.handle_button_up(
-            button, x, y, &mut self.ui_system, &mut self.game_state
+            &self.log, button, x, y, &mut self.ui_system, &self.object_classes,
+            &mut self.game_state,
         );
     }
 
+    fn key_down_event(
+        &mut self, _ctx: &mut Context,
+        keycode: Keycode, _keymod: Mod, _repeat: bool
+    ) {
+        self.input_handler.handle_key_down(&self.log, keycode, &mut self.game_state);
+    }
+
+    fn key_up_event(
+        &mut self, _ctx: &mut Context,
+        keycode: Keycode, _keymod: Mod, _repeat: bool
+    ) {
+        self.input_handler.handle_key_up(keycode);
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: i32, y: i32) {
+        self.input_handler.handle_wheel(y, &mut self.game_state);
+    }
+
     fn mouse_motion_event(
         &mut self, _ctx: &mut Context,
         _state: MouseState, x: i32, y: i32, xrel: i32, yrel: i32
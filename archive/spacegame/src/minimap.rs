@@ -0,0 +1,61 @@
+use {
+    ggez::graphics::{Rect},
+    nalgebra::{Point2, Vector2},
+
+    spacegame_game::state::{ship::{Ship}},
+};
+
+/// The largest the minimap can be on either axis, in pixels.
+const MAX_SIZE: f32 = 180.0;
+/// Distance between the minimap and the edges of the screen, in pixels.
+const MARGIN: f32 = 8.0;
+
+/// Where the minimap is on screen, and how positions on it map to the ship.
+pub struct MinimapLayout {
+    pub area: Rect,
+    pub pixels_per_tile: f32,
+}
+
+impl MinimapLayout {
+    /// Places the minimap in the bottom right corner, scaled to fit the whole ship.
+    pub fn new(screen_size: Vector2<i32>, ship: &Ship) -> Self {
+        let ship_size = ship.tiles.size();
+        let pixels_per_tile = MAX_SIZE / ship_size.x.max(ship_size.y).max(1) as f32;
+
+        let width = ship_size.x as f32 * pixels_per_tile;
+        let height = ship_size.y as f32 * pixels_per_tile;
+        let area = Rect::new(
+            screen_size.x as f32 - width - MARGIN,
+            screen_size.y as f32 - height - MARGIN,
+            width, height,
+        );
+
+        MinimapLayout {
+            area,
+            pixels_per_tile,
+        }
+    }
+
+    pub fn contains(&self, pixels: Point2<i32>) -> bool {
+        let (x, y) = (pixels.x as f32, pixels.y as f32);
+        x >= self.area.x && x < self.area.x + self.area.w &&
+            y >= self.area.y && y < self.area.y + self.area.h
+    }
+
+    /// Converts a world position to a screen position on the minimap.
+    pub fn world_to_screen(&self, world: Point2<f32>) -> Point2<f32> {
+        // The world goes up while the screen goes down
+        Point2::new(
+            self.area.x + world.x * self.pixels_per_tile,
+            self.area.y + self.area.h - world.y * self.pixels_per_tile,
+        )
+    }
+
+    /// Converts a screen position on the minimap to a world position.
+    pub fn screen_to_world(&self, pixels: Point2<i32>) -> Point2<f32> {
+        Point2::new(
+            (pixels.x as f32 - self.area.x) / self.pixels_per_tile,
+            (self.area.y + self.area.h - pixels.y as f32) / self.pixels_per_tile,
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    ggez::{
+        Context, GameResult,
+        graphics::{self, MeshBuilder, Rect},
+    },
+    nalgebra::{Point2},
+
+    spacegame_game::{
+        object_class::{ObjectClasses},
+        pathfinding::{Walkable},
+        state::{GameState, ship::{TileLayer}},
+    },
+    minimap::{MinimapLayout},
+    rendering::{add_rect},
+};
+
+/// Draws an overview of the whole ship in screen space, with where the camera is looking.
+pub fn draw_minimap(
+    ctx: &mut Context, object_classes: &ObjectClasses, game_state: &GameState,
+) -> GameResult<()> {
+    let ship = &game_state.ship;
+    let layout = MinimapLayout::new(game_state.camera.screen_size(), ship);
+    let tile_size = layout.pixels_per_tile;
+
+    // Sort tiles by what they look like, so every kind can be drawn in its own color
+    let mut hull_builder = MeshBuilder::new();
+    let mut floors_builder = MeshBuilder::new();
+    let mut walls_builder = MeshBuilder::new();
+    let mut doors_builder = MeshBuilder::new();
+    for position in ship.tiles.iter_pos() {
+        let tile = ship.tiles.get(position).unwrap();
+        let builder = match tile.layer {
+            TileLayer::Space => continue,
+            TileLayer::Hull => &mut hull_builder,
+            TileLayer::Floor => match tile.walkable(object_classes).unwrap() {
+                Walkable::Always => &mut floors_builder,
+                Walkable::Never => &mut walls_builder,
+                Walkable::Openable => &mut doors_builder,
+            },
+        };
+        let corner = layout.world_to_screen(
+            Point2::new(position.x as f32, position.y as f32 + 1.0)
+        );
+        add_rect(builder, corner, tile_size, tile_size);
+    }
+
+    let mut fires_builder = MeshBuilder::new();
+    for position in ship.fires.burning() {
+        let corner = layout.world_to_screen(
+            Point2::new(position.x as f32, position.y as f32 + 1.0)
+        );
+        add_rect(&mut fires_builder, corner, tile_size, tile_size);
+    }
+
+    let mut tasks_builder = MeshBuilder::new();
+    for (_, task) in ship.task_queue.tasks() {
+        let corner = layout.world_to_screen(
+            Point2::new(task.position.x as f32, task.position.y as f32 + 1.0)
+        );
+        add_rect(&mut tasks_builder, corner, tile_size, tile_size);
+    }
+
+    // Units are always at least a few pixels, so they don't disappear on large ships
+    let mut units_builder = MeshBuilder::new();
+    let unit_size = tile_size.max(3.0);
+    for unit in ship.units() {
+        let center = layout.world_to_screen(unit.position());
+        let corner = Point2::new(center.x - unit_size * 0.5, center.y - unit_size * 0.5);
+        add_rect(&mut units_builder, corner, unit_size, unit_size);
+    }
+
+    // Draw everything in screen space on top of the world
+    let screen_size = game_state.camera.screen_size();
+    graphics::set_screen_coordinates(
+        ctx, Rect::new(0.0, 0.0, screen_size.x as f32, screen_size.y as f32),
+    )?;
+
+    graphics::set_color(ctx, (20, 20, 30, 220).into())?;
+    graphics::rectangle(ctx, graphics::DrawMode::Fill, layout.area)?;
+
+    let meshes = [
+        (hull_builder, (60, 64, 76, 255)),
+        (floors_builder, (90, 90, 100, 255)),
+        (walls_builder, (190, 190, 200, 255)),
+        (doors_builder, (120, 170, 220, 255)),
+        (fires_builder, (255, 110, 30, 255)),
+        (tasks_builder, (255, 255, 255, 120)),
+        (units_builder, (255, 230, 120, 255)),
+    ];
+    for &(ref builder, color) in &meshes {
+        let mesh = builder.build(ctx)?;
+        graphics::set_color(ctx, color.into())?;
+        graphics::draw(ctx, &mesh, Point2::new(0.0, 0.0), 0.0)?;
+    }
+
+    // Show what part of the ship the camera is looking at, limited to the minimap itself
+    let (world_start, world_end) = game_state.camera.world_bounds();
+    let start = layout.world_to_screen(Point2::new(world_start.x, world_end.y));
+    let end = layout.world_to_screen(Point2::new(world_end.x, world_start.y));
+    let area_end = Point2::new(layout.area.x + layout.area.w, layout.area.y + layout.area.h);
+    let start = Point2::new(start.x.max(layout.area.x), start.y.max(layout.area.y));
+    let end = Point2::new(end.x.min(area_end.x), end.y.min(area_end.y));
+
+    graphics::set_color(ctx, (255, 255, 255, 200).into())?;
+    graphics::rectangle(
+        ctx, graphics::DrawMode::Line(1.0),
+        Rect::new(start.x, start.y, end.x - start.x, end.y - start.y),
+    )?;
+
+    Ok(())
+}
@@ -1,3 +1,7 @@
+mod minimap;
+mod notifications;
+mod overlay;
+mod path_debug;
 mod ship;
 
 use {
@@ -17,14 +21,29 @@ use {
         object_class::{ObjectClasses},
         state::{
             normalize_area, GameState, BuildState, BuildDrag, BuildChoice, Camera,
-            ship::{Ship},
+            SelectionState, SelectionDrag,
+            ship::{Ship, TileLayer},
         },
     },
     ui::{UiSystem},
 };
 
+/// Scale for drawing tile sprites one tile large, the tile sheet has 64 pixels per tile. Zoom is
+/// handled by the camera's projection, so this stays the same at any zoom level.
+const TILE_SPRITE_SCALE: f32 = 1.0 / 64.0;
+
+/// Where in the tile sheet the sprite for a tile layer is, space doesn't have one.
+fn layer_uvs(layer: TileLayer) -> Option<Rect> {
+    match layer {
+        TileLayer::Space => None,
+        TileLayer::Hull => Some(Rect::new(0.5, 0.0, 0.125, 0.125)),
+        TileLayer::Floor => Some(Rect::new(0.0, 0.125, 0.125, 0.125)),
+    }
+}
+
 pub struct Renderer {
     fps_font: Font,
+    legend_font: Font,
     tiles_batch: SpriteBatch,
     rivr_cache: GgezRivrCache,
 }
@@ -32,12 +51,14 @@ pub struct Renderer {
 impl Renderer {
     pub fn new(ctx: &mut Context) -> GameResult<Self> {
         let fps_font = Font::new(ctx, "/DejaVuSansMono.ttf", 8)?;
+        let legend_font = Font::new(ctx, "/DejaVuSansMono.ttf", 10)?;
         let tiles_batch = SpriteBatch::new(Image::new(ctx, "/tiles.png")?);
 
         let rivr_cache = GgezRivrCache::new();
 
         Ok(Renderer {
             fps_font,
+            legend_font,
             tiles_batch,
             rivr_cache,
         })
@@ -47,7 +68,7 @@ impl Renderer {
         &mut self,
         ctx: &mut Context, ui_system: &mut UiSystem,
         object_classes: &ObjectClasses,
-        game_state: &mut GameState,
+        game_state: &mut GameState, interpolation: f32,
     ) -> GameResult<()> {
         graphics::set_background_color(ctx, (10, 10, 15).into());
         graphics::clear(ctx);
@@ -60,11 +81,14 @@ impl Renderer {
 
         // Draw everything in the world
         ship::draw_ship(
-            ctx, object_classes, game_state, &mut self.tiles_batch
+            ctx, object_classes, game_state, interpolation, &mut self.tiles_batch
         )?;
+        overlay::draw_overlay(ctx, object_classes, game_state)?;
+        path_debug::draw_path_debug(ctx, game_state)?;
         draw_build_graphics(
             ctx, object_classes, game_state, &mut self.tiles_batch
         )?;
+        draw_selection_box(ctx, &game_state.selection)?;
 
         // Render the UI
         {
@@ -74,6 +98,11 @@ impl Renderer {
             ).unwrap();
         }
 
+        // The minimap goes over the world and UI, it takes its own clicks
+        minimap::draw_minimap(ctx, object_classes, game_state)?;
+        overlay::draw_legend(ctx, &self.legend_font, game_state)?;
+        notifications::draw_notifications(ctx, &self.legend_font, game_state)?;
+
         // Draw an FPS counter over everything else for debugging
         let fps = timer::get_fps(ctx);
         let text = Text::new(ctx, &format!("FPS: {:.2}", fps), &self.fps_font)?;
@@ -85,6 +114,22 @@ impl Renderer {
     }
 }
 
+fn draw_selection_box(ctx: &mut Context, selection: &SelectionState) -> GameResult<()> {
+    if let SelectionDrag::Dragging { start, end } = selection.drag {
+        let rect = Rect::new(
+            start.x.min(end.x), start.y.min(end.y),
+            (end.x - start.x).abs(), (end.y - start.y).abs(),
+        );
+
+        graphics::set_color(ctx, (255, 230, 120, 40).into())?;
+        graphics::rectangle(ctx, graphics::DrawMode::Fill, rect)?;
+        graphics::set_color(ctx, (255, 230, 120, 200).into())?;
+        graphics::rectangle(ctx, graphics::DrawMode::Line(0.05), rect)?;
+    }
+
+    Ok(())
+}
+
 pub fn draw_build_graphics(
     ctx: &mut Context,
     object_classes: &ObjectClasses, game_state: &GameState, tiles: &mut SpriteBatch,
@@ -106,6 +151,10 @@ fn draw_grid(
     let world_bounds = camera.world_bounds();
     let bounds = ship.tiles.bounds(world_bounds.0, world_bounds.1);
 
+    // Keep the lines the same width on screen regardless of zoom
+    let horizontal_width = 1.6 / camera.pixels_per_tile();
+    let vertical_width = 1.28 / camera.pixels_per_tile();
+
     // Draw a build grid
     let mut grid_builder = MeshBuilder::new();
     for y in bounds.start.y..(bounds.end.y+1) {
@@ -114,7 +163,7 @@ fn draw_grid(
                 Point2::new(bounds.start.x as f32, y as f32),
                 Point2::new(bounds.end.x as f32, y as f32)
             ],
-            0.025
+            horizontal_width
         );
     }
     for x in bounds.start.x..(bounds.end.x+1) {
@@ -123,7 +172,7 @@ fn draw_grid(
                 Point2::new(x as f32, bounds.start.y as f32),
                 Point2::new(x as f32, bounds.end.y as f32)
             ],
-            0.02
+            vertical_width
         );
     }
     let grid_mesh = grid_builder.build(ctx)?;
@@ -140,8 +189,8 @@ fn draw_build_placeholder(
 ) -> GameResult<()> {
     // Check what we need to draw
     let uvs = match build_state.choice {
-        BuildChoice::Floor =>
-            Some(Rect::new(0.0, 0.5, 0.5, 0.5)),
+        BuildChoice::Floor => layer_uvs(TileLayer::Floor),
+        BuildChoice::Hull => layer_uvs(TileLayer::Hull),
         BuildChoice::Object(id) =>
             Some(object_classes.get(id).unwrap().uvs),
         _ => None
@@ -166,7 +215,7 @@ fn draw_build_placeholder(
                 tiles.add(DrawParam {
                     src: uvs,
                     dest: Point2::new(fx, fy + 1.0),
-                    scale: Point2::new(1.0 / 64.0, -1.0 / 64.0),
+                    scale: Point2::new(TILE_SPRITE_SCALE, -TILE_SPRITE_SCALE),
                     .. Default::default()
                 });
             }
@@ -188,3 +237,15 @@ fn draw_build_placeholder(
 
     Ok(())
 }
+
+fn add_rect(builder: &mut MeshBuilder, corner: Point2<f32>, width: f32, height: f32) {
+    builder.triangles(&[
+        Point2::new(corner.x, corner.y),
+        Point2::new(corner.x + width, corner.y),
+        Point2::new(corner.x, corner.y + height),
+
+        Point2::new(corner.x + width, corner.y + height),
+        Point2::new(corner.x, corner.y + height),
+        Point2::new(corner.x + width, corner.y),
+    ]);
+}
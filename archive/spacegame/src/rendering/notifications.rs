@@ -0,0 +1,45 @@
+use {
+    ggez::{
+        Context, GameResult,
+        graphics::{self, Font, Text, Rect},
+    },
+    nalgebra::{Point2},
+
+    spacegame_game::{state::{GameState}},
+};
+
+/// Shows recent notifications in the top right corner of the screen, newest at the bottom.
+pub fn draw_notifications(
+    ctx: &mut Context, font: &Font, game_state: &GameState,
+) -> GameResult<()> {
+    let entries = game_state.notifications.entries();
+    if entries.is_empty() {
+        return Ok(())
+    }
+
+    let screen_size = game_state.camera.screen_size();
+    graphics::set_screen_coordinates(
+        ctx, Rect::new(0.0, 0.0, screen_size.x as f32, screen_size.y as f32),
+    )?;
+
+    let row_height = 18.0;
+    let width = 420.0;
+    let corner = Point2::new(screen_size.x as f32 - width - 8.0, 40.0);
+
+    for (i, entry) in entries.iter().enumerate() {
+        // Fade out during the last second
+        let alpha = entry.time_left.min(1.0);
+        let y = corner.y + row_height * i as f32;
+
+        graphics::set_color(ctx, (20, 20, 30, (200.0 * alpha) as u8).into())?;
+        graphics::rectangle(
+            ctx, graphics::DrawMode::Fill, Rect::new(corner.x, y, width, row_height - 2.0),
+        )?;
+
+        let text = Text::new(ctx, &entry.text, font)?;
+        graphics::set_color(ctx, (255, 255, 255, (255.0 * alpha) as u8).into())?;
+        graphics::draw(ctx, &text, Point2::new(corner.x + 4.0, y + 2.0), 0.0)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use {
+    ggez::{
+        Context, GameResult,
+        graphics::{self, Color, Font, Text, MeshBuilder, Rect},
+    },
+    nalgebra::{Point2},
+
+    spacegame_game::{
+        object_class::{ObjectClasses},
+        state::{GameState, ship::{OverlayValue}},
+    },
+    rendering::{add_rect},
+};
+
+/// Colors for category values, repeating when there's more categories than colors.
+const CATEGORY_COLORS: [(u8, u8, u8); 8] = [
+    (80, 200, 120),
+    (230, 190, 60),
+    (220, 80, 80),
+    (80, 150, 230),
+    (200, 100, 220),
+    (60, 210, 210),
+    (240, 140, 60),
+    (160, 160, 160),
+];
+/// How many different colors scale values are split into.
+const SCALE_STEPS: f32 = 8.0;
+
+/// Tints the tiles in view by the value the current overlay has for them.
+pub fn draw_overlay(
+    ctx: &mut Context, object_classes: &ObjectClasses, game_state: &GameState,
+) -> GameResult<()> {
+    let overlay = if let Some(overlay) = game_state.overlay { overlay } else { return Ok(()) };
+    let ship = &game_state.ship;
+
+    // Tiles with the same color go in the same mesh, so this is a handful of draws at most
+    let mut builders: Vec<((u8, u8, u8), MeshBuilder)> = Vec::new();
+    let world_bounds = game_state.camera.world_bounds();
+    for position in ship.tiles.bounds(world_bounds.0, world_bounds.1).iter() {
+        let value = overlay.value(position, ship, object_classes).unwrap();
+        let color = if let Some(value) = value { value_color(value) } else { continue };
+
+        let index = if let Some(index) = builders.iter().position(|&(c, _)| c == color) {
+            index
+        } else {
+            builders.push((color, MeshBuilder::new()));
+            builders.len() - 1
+        };
+        add_rect(
+            &mut builders[index].1,
+            Point2::new(position.x as f32, position.y as f32), 1.0, 1.0,
+        );
+    }
+
+    for (color, builder) in builders {
+        let mesh = builder.build(ctx)?;
+        graphics::set_color(ctx, (color.0, color.1, color.2, 110).into())?;
+        graphics::draw(ctx, &mesh, Point2::new(0.0, 0.0), 0.0)?;
+    }
+
+    Ok(())
+}
+
+/// Explains the colors of the current overlay in the bottom left corner of the screen.
+pub fn draw_legend(ctx: &mut Context, font: &Font, game_state: &GameState) -> GameResult<()> {
+    let overlay = if let Some(overlay) = game_state.overlay { overlay } else { return Ok(()) };
+
+    let screen_size = game_state.camera.screen_size();
+    graphics::set_screen_coordinates(
+        ctx, Rect::new(0.0, 0.0, screen_size.x as f32, screen_size.y as f32),
+    )?;
+
+    let legend = overlay.legend();
+    let row_height = 16.0;
+    let height = row_height * (legend.len() + 1) as f32 + 8.0;
+    let corner = Point2::new(8.0, screen_size.y as f32 - height - 16.0);
+
+    graphics::set_color(ctx, (20, 20, 30, 220).into())?;
+    graphics::rectangle(
+        ctx, graphics::DrawMode::Fill, Rect::new(corner.x, corner.y, 240.0, height),
+    )?;
+
+    let title = Text::new(ctx, &format!("Overlay: {}", overlay.describe()), font)?;
+    graphics::set_color(ctx, (255, 255, 255, 255).into())?;
+    graphics::draw(ctx, &title, Point2::new(corner.x + 4.0, corner.y + 4.0), 0.0)?;
+
+    for (i, (value, text)) in legend.iter().enumerate() {
+        let y = corner.y + 4.0 + row_height * (i + 1) as f32;
+        let color = value_color(*value);
+
+        graphics::set_color(ctx, Color::from((color.0, color.1, color.2, 255)))?;
+        graphics::rectangle(
+            ctx, graphics::DrawMode::Fill, Rect::new(corner.x + 4.0, y + 1.0, 10.0, 10.0),
+        )?;
+
+        let text = Text::new(ctx, text, font)?;
+        graphics::set_color(ctx, (255, 255, 255, 255).into())?;
+        graphics::draw(ctx, &text, Point2::new(corner.x + 20.0, y), 0.0)?;
+    }
+
+    Ok(())
+}
+
+fn value_color(value: OverlayValue) -> (u8, u8, u8) {
+    match value {
+        OverlayValue::Category(category) =>
+            CATEGORY_COLORS[category as usize % CATEGORY_COLORS.len()],
+        OverlayValue::Scale(scale) => {
+            // Going from green to red, in steps so similar values can be drawn together
+            let scale = (scale.max(0.0).min(1.0) * SCALE_STEPS).round() / SCALE_STEPS;
+            (
+                (80.0 + 150.0 * scale) as u8,
+                (210.0 - 150.0 * scale) as u8,
+                80,
+            )
+        },
+    }
+}
@@ -0,0 +1,87 @@
+use {
+    ggez::{
+        Context, GameResult,
+        graphics::{self, MeshBuilder},
+    },
+    nalgebra::{Point2},
+
+    spacegame_game::{state::{GameState}},
+    rendering::{add_rect},
+};
+
+/// Draws the paths units are following, and what the last failed search looked at.
+pub fn draw_path_debug(ctx: &mut Context, game_state: &GameState) -> GameResult<()> {
+    if !game_state.path_debug {
+        return Ok(())
+    }
+
+    let ship = &game_state.ship;
+    // Keep lines the same width on screen regardless of zoom
+    let line_width = 2.0 / game_state.camera.pixels_per_tile();
+
+    // The failed search goes below the paths, it can cover a lot of tiles
+    let failed_search = ship.pathfinder().last_failed_search();
+    if let Some(ref search) = *failed_search {
+        let mut explored_builder = MeshBuilder::new();
+        for node in &search.explored {
+            add_rect(
+                &mut explored_builder,
+                Point2::new(node.x as f32 + 0.1, node.y as f32 + 0.1), 0.8, 0.8,
+            );
+        }
+
+        // Excluded diagonals go halfway towards the neighbor they couldn't reach
+        let mut corners_builder = MeshBuilder::new();
+        for &(node, neighbor) in &search.corner_exclusions {
+            let from = tile_center(node.x, node.y);
+            let to = Point2::new(
+                from.x + (neighbor.x - node.x) as f32 * 0.5,
+                from.y + (neighbor.y - node.y) as f32 * 0.5,
+            );
+            corners_builder.line(&[from, to], line_width);
+        }
+
+        let mut ends_builder = MeshBuilder::new();
+        for position in &[search.start, search.goal] {
+            add_rect(
+                &mut ends_builder,
+                Point2::new(position.x as f32 + 0.3, position.y as f32 + 0.3), 0.4, 0.4,
+            );
+        }
+
+        let explored_mesh = explored_builder.build(ctx)?;
+        let corners_mesh = corners_builder.build(ctx)?;
+        let ends_mesh = ends_builder.build(ctx)?;
+
+        graphics::set_color(ctx, (120, 160, 255, 60).into())?;
+        graphics::draw(ctx, &explored_mesh, Point2::new(0.0, 0.0), 0.0)?;
+        graphics::set_color(ctx, (255, 90, 90, 220).into())?;
+        graphics::draw(ctx, &corners_mesh, Point2::new(0.0, 0.0), 0.0)?;
+        graphics::set_color(ctx, (255, 90, 90, 255).into())?;
+        graphics::draw(ctx, &ends_mesh, Point2::new(0.0, 0.0), 0.0)?;
+    }
+
+    // Paths are stored from the goal back to the next tile, so draw them from the unit outwards
+    let mut paths_builder = MeshBuilder::new();
+    for unit in ship.units() {
+        let path = if let Some(path) = unit.path() { path } else { continue };
+        if path.is_empty() {
+            continue
+        }
+
+        let position = unit.position();
+        let mut points = vec!(Point2::new(position.x, position.y));
+        points.extend(path.iter().rev().map(|node| tile_center(node.x, node.y)));
+        paths_builder.line(&points, line_width);
+    }
+
+    let paths_mesh = paths_builder.build(ctx)?;
+    graphics::set_color(ctx, (120, 255, 160, 200).into())?;
+    graphics::draw(ctx, &paths_mesh, Point2::new(0.0, 0.0), 0.0)?;
+
+    Ok(())
+}
+
+fn tile_center(x: i32, y: i32) -> Point2<f32> {
+    Point2::new(x as f32 + 0.5, y as f32 + 0.5)
+}
@@ -7,20 +7,23 @@ use {
 
     spacegame_game::{
         object_class::{ObjectClasses},
-        state::{GameState, Camera, ship::{Ship}},
+        state::{GameState, Camera, SelectionState, ship::{Ship, Unit, Facing, Activity}},
     },
+    rendering::{TILE_SPRITE_SCALE, layer_uvs, add_rect},
 };
 
 pub fn draw_ship(
     ctx: &mut Context,
     object_classes: &ObjectClasses,
-    game_state: &GameState,
+    game_state: &GameState, interpolation: f32,
     tiles_batch: &mut SpriteBatch,
 ) -> GameResult<()> {
 
     draw_tiles(ctx, &game_state.ship, &game_state.camera, object_classes, tiles_batch)?;
+    draw_damage(ctx, &game_state.ship, &game_state.camera)?;
+    draw_fires(ctx, &game_state.ship, &game_state.camera)?;
     draw_tasks(ctx, &game_state.ship)?;
-    draw_units(ctx, &game_state.ship)?;
+    draw_units(ctx, &game_state.ship, &game_state.selection, interpolation, tiles_batch)?;
 
     Ok(())
 }
@@ -36,24 +39,27 @@ fn draw_tiles(
 
         let (fx, fy) = (position.x as f32, position.y as f32);
 
-        // Add graphic for the floor
-        if tile.floor {
+        // Add graphic for the floor or hull
+        if let Some(uvs) = layer_uvs(tile.layer) {
             tiles_batch.add(DrawParam {
-                src: Rect::new(0.0, 0.5, 0.5, 0.5),
+                src: uvs,
                 dest: Point2::new(fx, fy + 1.0),
-                scale: Point2::new(1.0 / 64.0, -1.0 / 64.0),
+                scale: Point2::new(TILE_SPRITE_SCALE, -TILE_SPRITE_SCALE),
                 .. Default::default()
             });
         }
 
         // Add graphic for objects
         if let Some(ref object) = tile.object {
-            let uvs = object_classes.get(object.class).unwrap().uvs;
+            // The sprite can depend on the object's state, such as how far a door is open, and
+            // on its neighbors, such as walls connecting to each other
+            let uvs = object_classes.get(object.class).unwrap()
+                .tile_uvs(object, &ship.tiles, position);
 
             tiles_batch.add(DrawParam {
                 src: uvs,
                 dest: Point2::new(fx, fy + 1.0),
-                scale: Point2::new(1.0 / 64.0, -1.0 / 64.0),
+                scale: Point2::new(TILE_SPRITE_SCALE, -TILE_SPRITE_SCALE),
                 .. Default::default()
             });
         }
@@ -66,16 +72,73 @@ fn draw_tiles(
     Ok(())
 }
 
+/// Marks damaged tiles and objects, and shows holes where tiles have been breached.
+fn draw_damage(ctx: &mut Context, ship: &Ship, camera: &Camera) -> GameResult<()> {
+    let mut damaged_builder = MeshBuilder::new();
+    let mut breached_builder = MeshBuilder::new();
+
+    let world_bounds = camera.world_bounds();
+    for position in ship.tiles.bounds(world_bounds.0, world_bounds.1).iter() {
+        let tile = ship.tiles.get(position).unwrap();
+        let corner = Point2::new(position.x as f32, position.y as f32);
+
+        if tile.is_breached() {
+            add_rect(&mut breached_builder, corner, 1.0, 1.0);
+        } else if tile.damage > 0.0 ||
+            tile.object.as_ref().map(|object| object.damage > 0.0).unwrap_or(false) {
+            add_rect(&mut damaged_builder, corner, 1.0, 1.0);
+        }
+    }
+
+    let damaged_mesh = damaged_builder.build(ctx)?;
+    let breached_mesh = breached_builder.build(ctx)?;
+
+    graphics::set_color(ctx, (255, 90, 40, 60).into())?;
+    graphics::draw(ctx, &damaged_mesh, Point2::new(0.0, 0.0), 0.0)?;
+
+    graphics::set_color(ctx, (10, 10, 15, 220).into())?;
+    graphics::draw(ctx, &breached_mesh, Point2::new(0.0, 0.0), 0.0)?;
+
+    Ok(())
+}
+
+/// Draws fires brighter the more intense they are, in a few steps so they batch together.
+fn draw_fires(ctx: &mut Context, ship: &Ship, camera: &Camera) -> GameResult<()> {
+    let mut builders = [MeshBuilder::new(), MeshBuilder::new(), MeshBuilder::new()];
+
+    let world_bounds = camera.world_bounds();
+    for position in ship.tiles.bounds(world_bounds.0, world_bounds.1).iter() {
+        let intensity = ship.fires.intensity(position);
+        if intensity <= 0.0 {
+            continue
+        }
+
+        let step = ((intensity * builders.len() as f32) as usize).min(builders.len() - 1);
+        let corner = Point2::new(position.x as f32, position.y as f32);
+        add_rect(&mut builders[step], corner, 1.0, 1.0);
+    }
+
+    let colors = [(255, 200, 60, 90), (255, 140, 30, 140), (255, 70, 20, 190)];
+    for (builder, color) in builders.iter().zip(colors.iter()) {
+        let mesh = builder.build(ctx)?;
+        graphics::set_color(ctx, (*color).into())?;
+        graphics::draw(ctx, &mesh, Point2::new(0.0, 0.0), 0.0)?;
+    }
+
+    Ok(())
+}
+
 fn draw_tasks(
     ctx: &mut Context, ship: &Ship
 ) -> GameResult<()> {
     let mut tasks_builder = MeshBuilder::new();
     let mut unreachable_tasks_builder = MeshBuilder::new();
+    let mut progress_builder = MeshBuilder::new();
 
     for (_, task) in ship.task_queue.tasks() {
         let (fx, fy) = (task.position.x as f32, task.position.y as f32);
 
-        let builder = if !task.unreachable {
+        let builder = if task.unreachable.is_none() {
             &mut tasks_builder
         } else {
             &mut unreachable_tasks_builder
@@ -91,10 +154,26 @@ fn draw_tasks(
             Point2::new(fx + 0.25, fy + 0.75),
             Point2::new(fx + 0.75, fy + 0.25),
         ]);
+
+        // Add a bar for how far along the work is
+        let progress = task.progress();
+        if progress > 0.0 {
+            let end = fx + 0.1 + 0.8 * progress;
+            progress_builder.triangles(&[
+                Point2::new(fx + 0.1, fy + 0.1),
+                Point2::new(end, fy + 0.1),
+                Point2::new(fx + 0.1, fy + 0.2),
+
+                Point2::new(end, fy + 0.2),
+                Point2::new(fx + 0.1, fy + 0.2),
+                Point2::new(end, fy + 0.1),
+            ]);
+        }
     }
 
     let tasks_mesh = tasks_builder.build(ctx)?;
     let unreachable_tasks_mesh = unreachable_tasks_builder.build(ctx)?;
+    let progress_mesh = progress_builder.build(ctx)?;
 
     graphics::set_color(ctx, (255, 255, 255, 25).into())?;
     graphics::draw(ctx, &tasks_mesh, Point2::new(0.0, 0.0), 0.0)?;
@@ -102,29 +181,71 @@ fn draw_tasks(
     graphics::set_color(ctx, (255, 120, 120, 50).into())?;
     graphics::draw(ctx, &unreachable_tasks_mesh, Point2::new(0.0, 0.0), 0.0)?;
 
+    graphics::set_color(ctx, (120, 220, 120, 200).into())?;
+    graphics::draw(ctx, &progress_mesh, Point2::new(0.0, 0.0), 0.0)?;
+
     Ok(())
 }
 
 fn draw_units(
-    ctx: &mut Context, ship: &Ship
+    ctx: &mut Context, ship: &Ship, selection: &SelectionState, interpolation: f32,
+    tiles_batch: &mut SpriteBatch,
 ) -> GameResult<()> {
-    let mut units_builder = MeshBuilder::new();
+    let mut selected_builder = MeshBuilder::new();
     for unit in ship.units() {
-        let pos = unit.position();
-        units_builder.triangles(&[
-            Point2::new(pos.x - 0.4, pos.y - 0.4),
-            Point2::new(pos.x + 0.4, pos.y - 0.4),
-            Point2::new(pos.x - 0.4, pos.y + 0.4),
-
-            Point2::new(pos.x + 0.4, pos.y + 0.4),
-            Point2::new(pos.x - 0.4, pos.y + 0.4),
-            Point2::new(pos.x + 0.4, pos.y - 0.4),
-        ]);
+        let pos = unit.interpolated_position(interpolation);
+
+        // Add a highlight behind selected units
+        if selection.is_selected(unit.id()) {
+            selected_builder.triangles(&[
+                Point2::new(pos.x - 0.5, pos.y - 0.5),
+                Point2::new(pos.x + 0.5, pos.y - 0.5),
+                Point2::new(pos.x - 0.5, pos.y + 0.5),
+
+                Point2::new(pos.x + 0.5, pos.y + 0.5),
+                Point2::new(pos.x - 0.5, pos.y + 0.5),
+                Point2::new(pos.x + 0.5, pos.y - 0.5),
+            ]);
+        }
+
+        tiles_batch.add(DrawParam {
+            src: unit_uvs(unit),
+            dest: Point2::new(pos.x - 0.5, pos.y + 0.5),
+            scale: Point2::new(TILE_SPRITE_SCALE, -TILE_SPRITE_SCALE),
+            .. Default::default()
+        });
     }
-    let units_mesh = units_builder.build(ctx)?;
+    let selected_mesh = selected_builder.build(ctx)?;
+
+    graphics::set_color(ctx, (255, 230, 120, 120).into())?;
+    graphics::draw(ctx, &selected_mesh, Point2::new(0.0, 0.0), 0.0)?;
 
-    graphics::set_color(ctx, (150, 200, 150).into())?;
-    graphics::draw(ctx, &units_mesh, Point2::new(0.0, 0.0), 0.0)?;
+    graphics::set_color(ctx, (255, 255, 255).into())?;
+    graphics::draw(ctx, tiles_batch, Point2::new(0.0, 0.0), 0.0)?;
+    tiles_batch.clear();
 
     Ok(())
 }
+
+/// Picks the frame of the unit sprite sheet for what the unit is doing. Each facing has its own
+/// row, with two frames each for idle, walking and working. Animations run on game time, so they
+/// stop when the game is paused.
+fn unit_uvs(unit: &Unit) -> Rect {
+    let row = match unit.facing() {
+        Facing::Down => 3,
+        Facing::Up => 4,
+        Facing::Left => 5,
+        Facing::Right => 6,
+    };
+    let (first_column, frames_per_second) = match unit.activity() {
+        Activity::Idle => (0, 1.0),
+        Activity::Walking => (2, 6.0),
+        Activity::Working => (4, 4.0),
+    };
+    let frame = (unit.activity_time() * frames_per_second) as u32 % 2;
+
+    Rect::new(
+        (first_column + frame) as f32 * 0.125, row as f32 * 0.125,
+        0.125, 0.125,
+    )
+}
@@ -1,3 +1,5 @@
+mod roster;
+mod task_list;
 mod top_bar;
 
 use {
@@ -6,17 +8,21 @@ use {
     ggez::{Context, GameResult},
     slog::{Logger},
     rivr::{
-        attributes::{PanelSize, PanelBox, Orientation},
+        attributes::{PanelSize, AxisSize, PanelBox, Orientation, Srgba},
         input::{FrameCollision},
-        panels::{StackPanel},
-        Ui,
+        panels::{StackPanel, LabelPanel, ButtonPanel, EmptyPanel},
+        Ui, Event, PanelId, FontId,
     },
 
     spacegame_game::{
         object_class::{ObjectClasses},
         state::{GameState},
     },
-    ui::top_bar::{TopBar},
+    ui::{
+        roster::{CrewRoster},
+        task_list::{TaskList},
+        top_bar::{TopBar},
+    },
 };
 
 pub struct UiSystem {
@@ -24,6 +30,8 @@ pub struct UiSystem {
     pub frame: FrameCollision,
 
     top_bar: TopBar,
+    roster: CrewRoster,
+    task_list: TaskList,
 }
 
 impl UiSystem {
@@ -38,6 +46,25 @@ impl UiSystem {
 
         // Set up the UI itself
         let (top_bar, top_bar_id) = TopBar::new(&mut ui, font, object_classes);
+        let (roster, roster_id) = CrewRoster::new(&mut ui, font);
+        let (task_list, task_list_id) = TaskList::new(&mut ui, font);
+
+        // Panels below the top bar go on the sides, leaving the middle free to see the ship
+        let spacer = EmptyPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Min),
+            PanelBox::default(),
+        );
+        let spacer_id = ui.add_panel(spacer);
+
+        let mut content = StackPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Min),
+            PanelBox::default(),
+            Orientation::Horizontal, 0.0,
+        );
+        content.add_child(roster_id);
+        content.add_child(spacer_id);
+        content.add_child(task_list_id);
+        let content_id = ui.add_panel(content);
 
         let mut root = StackPanel::new(
             PanelSize::max(),
@@ -45,6 +72,7 @@ impl UiSystem {
             Orientation::Vertical, 0.0,
         );
         root.add_child(top_bar_id);
+        root.add_child(content_id);
         ui.add_root(root).unwrap();
 
         Ok(UiSystem {
@@ -52,14 +80,68 @@ impl UiSystem {
             frame: FrameCollision::new(),
 
             top_bar,
+            roster,
+            task_list,
         })
     }
 
     pub fn update(
-        &self, log: &Logger, ctx: &mut Context, game_state: &mut GameState
+        &mut self, log: &Logger, ctx: &mut Context,
+        object_classes: &ObjectClasses, game_state: &mut GameState,
     ) -> GameResult<()> {
-        self.top_bar.update(log, ctx, game_state)?;
+        self.top_bar.update(log, ctx, &mut self.ui, game_state)?;
+        self.roster.update(&mut self.ui, object_classes, game_state);
+        self.task_list.update(log, &mut self.ui, object_classes, game_state);
 
         Ok(())
     }
 }
+
+/// Changes the text shown by a label panel.
+pub fn set_label_text(ui: &mut Ui, label_id: PanelId, text: &str) {
+    ui.get_mut::<LabelPanel>(label_id)
+        .expect("Panel is not a label")
+        .set_text(text);
+}
+
+/// Adds a child to a stack panel that's already been added to the UI.
+pub fn add_child(ui: &mut Ui, stack_id: PanelId, child_id: PanelId) {
+    ui.get_mut::<StackPanel>(stack_id)
+        .expect("Panel is not a stack panel")
+        .add_child(child_id);
+}
+
+/// Removes a child from a stack panel that's already been added to the UI.
+pub fn remove_child(ui: &mut Ui, stack_id: PanelId, child_id: PanelId) {
+    ui.get_mut::<StackPanel>(stack_id)
+        .expect("Panel is not a stack panel")
+        .remove_child(child_id);
+}
+
+pub fn labeled_button(
+    ui: &mut Ui, text: &str, font: FontId
+) -> (PanelId, Event) {
+    sized_labeled_button(ui, text, font, 84.0)
+}
+
+pub fn sized_labeled_button(
+    ui: &mut Ui, text: &str, font: FontId, width: f32,
+) -> (PanelId, Event) {
+    let label = LabelPanel::new(ui, text, font, 12.0).unwrap();
+    let label_id = ui.add_panel(label);
+
+    let button = ButtonPanel::new(
+        PanelSize::absolute(width, 24.0),
+        PanelBox {
+            background: Some(Srgba::new(1.0, 1.0, 1.0, 1.0)),
+            background_hovering: Some(Srgba::new(0.95, 0.95, 0.95, 1.0)),
+            border_radius: 3.0,
+            .. PanelBox::default()
+        },
+        Some(label_id), 3.0,
+    );
+    let pressed = button.event_pressed();
+    let button_id = ui.add_panel(button);
+
+    (button_id, pressed)
+}
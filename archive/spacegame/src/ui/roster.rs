@@ -0,0 +1,141 @@
+use {
+    rivr::{
+        attributes::{PanelSize, AxisSize, PanelBox, Orientation, Srgba},
+        panels::{ButtonPanel, StackPanel, LabelPanel},
+        Ui, Event, PanelId, FontId,
+    },
+
+    spacegame_game::{
+        object_class::{ObjectClasses},
+        state::{GameState, ship::{Job}},
+    },
+    ui::{set_label_text, add_child, sized_labeled_button},
+};
+
+/// Lists all crew with what they're doing and how they're holding up, their health and the air
+/// where they are. Clicking on one selects them and moves the camera to them. Every crew member
+/// has buttons to toggle which jobs they're allowed to take on.
+pub struct CrewRoster {
+    font: FontId,
+    list_id: PanelId,
+    rows: Vec<RosterRow>,
+}
+
+struct RosterRow {
+    label_id: PanelId,
+    pressed: Event,
+    job_buttons: Vec<(Event, Job)>,
+    text: String,
+}
+
+impl CrewRoster {
+    pub fn new(ui: &mut Ui, font: FontId) -> (Self, PanelId) {
+        let list = StackPanel::new(
+            PanelSize::new(AxisSize::Absolute(640.0), AxisSize::Min),
+            PanelBox {
+                background: Some(Srgba::new(1.0, 1.0, 1.0, 0.6)),
+                .. PanelBox::default()
+            },
+            Orientation::Vertical, 2.0,
+        );
+        let list_id = ui.add_panel(list);
+
+        (CrewRoster {
+            font,
+            list_id,
+            rows: Vec::new(),
+        }, list_id)
+    }
+
+    pub fn update(
+        &mut self, ui: &mut Ui, object_classes: &ObjectClasses, game_state: &mut GameState,
+    ) {
+        // Add rows for any crew that joined since the last update
+        while self.rows.len() < game_state.ship.units().len() {
+            let (row, row_id) = RosterRow::new(ui, self.font);
+            add_child(ui, self.list_id, row_id);
+            self.rows.push(row);
+        }
+
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            // Presses are always checked, so they don't stay around if the crew member is gone
+            let toggled: Vec<_> = row.job_buttons.iter()
+                .filter(|&&(ref event, _)| event.check())
+                .map(|&(_, job)| job)
+                .collect();
+            if let Some(unit) = game_state.ship.units_mut().get_mut(i) {
+                for job in toggled {
+                    let enabled = unit.crew().is_job_enabled(job);
+                    unit.crew_mut().set_job_enabled(job, !enabled);
+                }
+            }
+
+            let text = if let Some(unit) = game_state.ship.units().get(i) {
+                if row.pressed.check() {
+                    game_state.selection.selected = vec!(unit.id());
+                    game_state.camera.set_position(unit.position());
+                }
+
+                let crew = unit.crew();
+                let jobs: Vec<_> = Job::all().iter()
+                    .filter(|job| crew.is_job_enabled(**job))
+                    .map(|job| job.describe())
+                    .collect();
+                let air = game_state.ship.atmosphere.oxygen(unit.tile_position()) * 100.0;
+                format!(
+                    "{} ({:?}, {:.0} hp, {:.0}% air) [{}]: {}",
+                    crew.name, crew.role, unit.health(), air, jobs.join(", "),
+                    unit.describe(&game_state.ship.task_queue, object_classes),
+                )
+            } else {
+                String::new()
+            };
+
+            // Only change the label when needed, changing it means laying out the text again
+            if row.text != text {
+                set_label_text(ui, row.label_id, &text);
+                row.text = text;
+            }
+        }
+    }
+}
+
+impl RosterRow {
+    fn new(ui: &mut Ui, font: FontId) -> (Self, PanelId) {
+        let label = LabelPanel::new(ui, "", font, 12.0).unwrap();
+        let label_id = ui.add_panel(label);
+
+        let button = ButtonPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Absolute(24.0)),
+            PanelBox {
+                background_hovering: Some(Srgba::new(1.0, 1.0, 1.0, 0.6)),
+                .. PanelBox::default()
+            },
+            Some(label_id), 3.0,
+        );
+        let pressed = button.event_pressed();
+        let button_id = ui.add_panel(button);
+
+        let mut row = StackPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Min),
+            PanelBox::default(),
+            Orientation::Horizontal, 3.0,
+        );
+        row.add_child(button_id);
+
+        let mut job_buttons = Vec::new();
+        for job in Job::all() {
+            let (job_button_id, job_pressed) = sized_labeled_button(ui, job.describe(), font, 56.0);
+            row.add_child(job_button_id);
+            job_buttons.push((job_pressed, *job));
+        }
+        let row_id = ui.add_panel(row);
+
+        (RosterRow {
+            label_id,
+            pressed,
+            job_buttons,
+            text: String::new(),
+        }, row_id)
+    }
+}
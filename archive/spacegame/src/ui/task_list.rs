@@ -0,0 +1,187 @@
+use {
+    std::cmp::{Reverse},
+
+    nalgebra::{Point2},
+    slog::{Logger},
+
+    rivr::{
+        attributes::{PanelSize, AxisSize, PanelBox, Orientation, Srgba},
+        panels::{StackPanel, LabelPanel},
+        Ui, Event, PanelId, FontId,
+    },
+
+    spacegame_game::{
+        object_class::{ObjectClasses},
+        state::{GameState, ship::{Ship, Task, TaskId, UnreachableReason}},
+    },
+    ui::{set_label_text, add_child, remove_child, labeled_button},
+};
+
+/// How many tasks are shown at most, the most important ones are shown first.
+const MAX_ROWS: usize = 12;
+
+/// Lists queued tasks with what's happening with them, and lets the player manage them.
+pub struct TaskList {
+    font: FontId,
+    list_id: PanelId,
+    rows: Vec<TaskRow>,
+}
+
+struct TaskRow {
+    row_id: PanelId,
+    /// If the row is in the list, only rows with a task are.
+    shown: bool,
+    label_id: PanelId,
+    cancel_pressed: Event,
+    priority_pressed: Event,
+    jump_pressed: Event,
+    task_id: Option<TaskId>,
+    text: String,
+}
+
+impl TaskList {
+    pub fn new(ui: &mut Ui, font: FontId) -> (Self, PanelId) {
+        let list = StackPanel::new(
+            PanelSize::new(AxisSize::Absolute(640.0), AxisSize::Min),
+            PanelBox {
+                background: Some(Srgba::new(1.0, 1.0, 1.0, 0.6)),
+                .. PanelBox::default()
+            },
+            Orientation::Vertical, 2.0,
+        );
+        let list_id = ui.add_panel(list);
+
+        (TaskList {
+            font,
+            list_id,
+            rows: Vec::new(),
+        }, list_id)
+    }
+
+    pub fn update(
+        &mut self, log: &Logger, ui: &mut Ui,
+        object_classes: &ObjectClasses, game_state: &mut GameState,
+    ) {
+        // Handle any buttons pressed for the tasks the rows were showing, presses always need to
+        // be checked so they don't stay around until the row shows a task again
+        for row in &self.rows {
+            let cancel_pressed = row.cancel_pressed.check();
+            let priority_pressed = row.priority_pressed.check();
+            let jump_pressed = row.jump_pressed.check();
+
+            let task_id = if let Some(task_id) = row.task_id { task_id } else { continue };
+            let ship = &mut game_state.ship;
+
+            if cancel_pressed {
+                info!(log, "Cancelling task {}", task_id.0);
+                if let Err(error) = ship.cancel_task(task_id) {
+                    warn!(log, "Couldn't cancel task {}: {:?}", task_id.0, error);
+                }
+            }
+            if priority_pressed {
+                if let Err(error) = ship.task_queue.raise_priority(task_id) {
+                    warn!(log, "Couldn't raise priority of task {}: {:?}", task_id.0, error);
+                }
+            }
+            if jump_pressed {
+                if let Some(task) = ship.task_queue.get(task_id) {
+                    game_state.camera.set_position(Point2::new(
+                        task.position.x as f32 + 0.5, task.position.y as f32 + 0.5,
+                    ));
+                }
+            }
+        }
+
+        // Show the most important tasks first, oldest first if they're equally important
+        let mut tasks: Vec<_> = game_state.ship.task_queue.tasks().iter().collect();
+        tasks.sort_by_key(|&(id, task)| (Reverse(task.priority), id.0));
+        tasks.truncate(MAX_ROWS);
+
+        while self.rows.len() < tasks.len() {
+            self.rows.push(TaskRow::new(ui, self.font));
+        }
+
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            let (task_id, text) = if let Some(&(id, task)) = tasks.get(i) {
+                (Some(*id), describe_task(*id, task, &game_state.ship, object_classes))
+            } else {
+                (None, String::new())
+            };
+
+            // Rows without a task are taken out of the list, tasks always fill the first rows so
+            // putting them back at the end keeps the rows in order
+            row.task_id = task_id;
+            if row.task_id.is_some() != row.shown {
+                if row.task_id.is_some() {
+                    add_child(ui, self.list_id, row.row_id);
+                } else {
+                    remove_child(ui, self.list_id, row.row_id);
+                }
+                row.shown = row.task_id.is_some();
+            }
+
+            if row.text != text {
+                set_label_text(ui, row.label_id, &text);
+                row.text = text;
+            }
+        }
+    }
+}
+
+impl TaskRow {
+    fn new(ui: &mut Ui, font: FontId) -> Self {
+        let label = LabelPanel::new(ui, "", font, 12.0).unwrap();
+        let label_id = ui.add_panel(label);
+
+        let (cancel_button_id, cancel_pressed) = labeled_button(ui, "Cancel", font);
+        let (priority_button_id, priority_pressed) = labeled_button(ui, "Priority +", font);
+        let (jump_button_id, jump_pressed) = labeled_button(ui, "Go To", font);
+
+        let mut row = StackPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Min),
+            PanelBox::default(),
+            Orientation::Horizontal, 3.0,
+        );
+        row.add_child(label_id);
+        row.add_child(cancel_button_id);
+        row.add_child(priority_button_id);
+        row.add_child(jump_button_id);
+        let row_id = ui.add_panel(row);
+
+        TaskRow {
+            row_id,
+            shown: false,
+            label_id,
+            cancel_pressed,
+            priority_pressed,
+            jump_pressed,
+            task_id: None,
+            text: String::new(),
+        }
+    }
+}
+
+fn describe_task(
+    id: TaskId, task: &Task, ship: &Ship, object_classes: &ObjectClasses,
+) -> String {
+    let status = if let Some(reason) = task.unreachable {
+        match reason {
+            UnreachableReason::Disconnected => "unreachable, no crew can get there".into(),
+            UnreachableReason::NoPath => "unreachable, no path found".into(),
+        }
+    } else if let Some(unit) = ship.units().iter()
+        .find(|unit| unit.assigned_task() == Some(id)) {
+        format!("{}, {:.0}%", unit.crew().name, task.progress() * 100.0)
+    } else if !ship.units().iter().any(|unit| task.can_be_done_by(unit.crew())) {
+        "waiting, no crew skilled enough".into()
+    } else if !ship.units().iter().any(|unit| unit.crew().is_job_enabled(task.job)) {
+        "waiting, no crew allowed to do this".into()
+    } else {
+        "waiting".into()
+    };
+
+    format!(
+        "[{}] {} at ({}, {}): {}",
+        task.priority, task.describe(object_classes), task.position.x, task.position.y, status,
+    )
+}
@@ -6,24 +6,29 @@ use {
 
     rivr::{
         attributes::{PanelSize, AxisSize, PanelBox, Orientation, Srgba},
-        panels::{ButtonPanel, StackPanel, LabelPanel, EmptyPanel},
+        panels::{StackPanel, EmptyPanel, LabelPanel},
         Ui, Event, PanelId, FontId,
     },
 
     spacegame_game::{
         object_class::{ObjectClasses, ObjectClassId},
-        state::{GameState, BuildState, BuildChoice},
+        state::{GameState, BuildState, BuildChoice, SpeedState, Speed, ship::{Overlay}},
     },
+    ui::{set_label_text, labeled_button, sized_labeled_button},
 };
 
 pub struct TopBar {
     buid_menu: BuildMenu,
+    speed_menu: SpeedMenu,
+    overlay_pressed: Event,
     game_menu: GameMenu,
 }
 
 impl TopBar {
     pub fn new(ui: &mut Ui, font: FontId, object_classes: &ObjectClasses) -> (Self, PanelId) {
         let (buid_menu, buid_menu_id) = BuildMenu::new(ui, font, object_classes);
+        let (speed_menu, speed_menu_id) = SpeedMenu::new(ui, font);
+        let (overlay_button_id, overlay_pressed) = labeled_button(ui, "Overlay", font);
         let (game_menu, game_menu_id) = GameMenu::new(ui, font);
 
         let spacer = EmptyPanel::new(
@@ -31,6 +36,11 @@ impl TopBar {
             PanelBox::default(),
         );
         let spacer_id = ui.add_panel(spacer);
+        let spacer2 = EmptyPanel::new(
+            PanelSize::new(AxisSize::Max, AxisSize::Max),
+            PanelBox::default(),
+        );
+        let spacer2_id = ui.add_panel(spacer2);
 
         let mut top_bar = StackPanel::new(
             PanelSize::new(AxisSize::Max, AxisSize::Absolute(0.0)),
@@ -42,27 +52,39 @@ impl TopBar {
         );
         top_bar.add_child(buid_menu_id);
         top_bar.add_child(spacer_id);
+        top_bar.add_child(speed_menu_id);
+        top_bar.add_child(spacer2_id);
+        top_bar.add_child(overlay_button_id);
         top_bar.add_child(game_menu_id);
         let top_bar_id = ui.add_panel(top_bar);
 
         (TopBar {
             buid_menu,
+            speed_menu,
+            overlay_pressed,
             game_menu,
         }, top_bar_id)
     }
 
     pub fn update(
-        &self, log: &Logger, ctx: &mut Context, game_state: &mut GameState
+        &mut self, log: &Logger, ctx: &mut Context, ui: &mut Ui, game_state: &mut GameState
     ) -> GameResult<()> {
         self.buid_menu.update(&mut game_state.build_state);
+        self.speed_menu.update(ui, &mut game_state.speed);
+        if self.overlay_pressed.check() {
+            game_state.overlay = Overlay::cycle(game_state.overlay);
+        }
         self.game_menu.update(log, ctx, game_state)?;
 
         Ok(())
     }
 }
 
+const BUILD_BUTTON_WIDTH: f32 = 76.0;
+
 struct BuildMenu {
     build_floor_pressed: Event,
+    build_hull_pressed: Event,
     destroy_pressed: Event,
     destroy_all_pressed: Event,
 
@@ -71,25 +93,33 @@ struct BuildMenu {
 
 impl BuildMenu {
     pub fn new(ui: &mut Ui, font: FontId, object_classes: &ObjectClasses) -> (Self, PanelId) {
+        let label = LabelPanel::new(ui, "Build:", font, 12.0).unwrap();
+        let label_id = ui.add_panel(label);
+
+        // Buttons are a bit narrower than usual so all objects fit in the bar
         let (build_floor_button_id, build_floor_pressed) =
-            labeled_button(ui, "Build Floor", font);
+            sized_labeled_button(ui, "Floor", font, BUILD_BUTTON_WIDTH);
+        let (build_hull_button_id, build_hull_pressed) =
+            sized_labeled_button(ui, "Hull", font, BUILD_BUTTON_WIDTH);
         let (destroy_button_id, destroy_pressed) =
-            labeled_button(ui, "Destroy", font);
+            sized_labeled_button(ui, "Destroy", font, BUILD_BUTTON_WIDTH);
         let (destroy_all_button_id, destroy_all_pressed) =
-            labeled_button(ui, "Destroy All", font);
+            sized_labeled_button(ui, "Destroy All", font, BUILD_BUTTON_WIDTH);
 
         let mut build_menu = StackPanel::new(
             PanelSize::new(AxisSize::Min, AxisSize::Min),
             PanelBox::default(),
             Orientation::Horizontal, 3.0,
         );
+        build_menu.add_child(label_id);
         build_menu.add_child(build_floor_button_id);
+        build_menu.add_child(build_hull_button_id);
 
         // Add all the buttons for different objects
         let mut build_buttons = Vec::new();
         for (id, class) in object_classes.entries().iter().enumerate() {
             let (build_button_id, build_pressed) =
-                labeled_button(ui, &format!("Build {}", class.friendly_name), font);
+                sized_labeled_button(ui, &class.friendly_name, font, BUILD_BUTTON_WIDTH);
             build_menu.add_child(build_button_id);
             build_buttons.push((build_pressed, ObjectClassId { id }));
         }
@@ -100,6 +130,7 @@ impl BuildMenu {
 
         (BuildMenu {
             build_floor_pressed,
+            build_hull_pressed,
             destroy_pressed,
             destroy_all_pressed,
 
@@ -111,6 +142,9 @@ impl BuildMenu {
         if self.build_floor_pressed.check() {
             build_state.choice = BuildChoice::Floor;
         }
+        if self.build_hull_pressed.check() {
+            build_state.choice = BuildChoice::Hull;
+        }
         if self.destroy_pressed.check() {
             build_state.choice = BuildChoice::Destroy;
         }
@@ -126,6 +160,66 @@ impl BuildMenu {
     }
 }
 
+struct SpeedMenu {
+    label_id: PanelId,
+    shown_speed: Option<Speed>,
+
+    speed_buttons: Vec<(Event, Speed)>,
+    step_pressed: Event,
+}
+
+impl SpeedMenu {
+    pub fn new(ui: &mut Ui, font: FontId) -> (Self, PanelId) {
+        let label = LabelPanel::new(ui, "", font, 12.0).unwrap();
+        let label_id = ui.add_panel(label);
+
+        let mut speed_menu = StackPanel::new(
+            PanelSize::new(AxisSize::Min, AxisSize::Min),
+            PanelBox::default(),
+            Orientation::Horizontal, 3.0,
+        );
+        speed_menu.add_child(label_id);
+
+        let mut speed_buttons = Vec::new();
+        for speed in Speed::all() {
+            let text = if *speed == Speed::Paused { "||" } else { speed.describe() };
+            let (button_id, pressed) = sized_labeled_button(ui, text, font, 36.0);
+            speed_menu.add_child(button_id);
+            speed_buttons.push((pressed, *speed));
+        }
+
+        let (step_button_id, step_pressed) = sized_labeled_button(ui, "Step", font, 48.0);
+        speed_menu.add_child(step_button_id);
+        let speed_menu_id = ui.add_panel(speed_menu);
+
+        (SpeedMenu {
+            label_id,
+            shown_speed: None,
+
+            speed_buttons,
+            step_pressed,
+        }, speed_menu_id)
+    }
+
+    pub fn update(&mut self, ui: &mut Ui, speed_state: &mut SpeedState) {
+        for (event, speed) in &self.speed_buttons {
+            if event.check() {
+                speed_state.set_speed(*speed);
+            }
+        }
+        if self.step_pressed.check() {
+            speed_state.queue_step();
+        }
+
+        // Speed can also change through hotkeys, so keep the label up to date
+        let speed = speed_state.speed();
+        if self.shown_speed != Some(speed) {
+            set_label_text(ui, self.label_id, &format!("Speed: {}", speed.describe()));
+            self.shown_speed = Some(speed);
+        }
+    }
+}
+
 struct GameMenu {
     new_pressed: Event,
     save_pressed: Event,
@@ -135,11 +229,11 @@ struct GameMenu {
 impl GameMenu {
     pub fn new(ui: &mut Ui, font: FontId) -> (Self, PanelId) {
         let (new_button_id, new_pressed) =
-            labeled_button(ui, "New", font);
+            sized_labeled_button(ui, "New", font, 48.0);
         let (save_button_id, save_pressed) =
-            labeled_button(ui, "Save", font);
+            sized_labeled_button(ui, "Save", font, 48.0);
         let (load_button_id, load_pressed) =
-            labeled_button(ui, "Load", font);
+            sized_labeled_button(ui, "Load", font, 48.0);
 
         let mut game_menu = StackPanel::new(
             PanelSize::new(AxisSize::Min, AxisSize::Min),
@@ -187,25 +281,3 @@ impl GameMenu {
         Ok(())
     }
 }
-
-fn labeled_button(
-    ui: &mut Ui, text: &str, font: FontId
-) -> (PanelId, Event) {
-    let label = LabelPanel::new(ui, text, font, 12.0).unwrap();
-    let label_id = ui.add_panel(label);
-
-    let button = ButtonPanel::new(
-        PanelSize::absolute(84.0, 24.0),
-        PanelBox {
-            background: Some(Srgba::new(1.0, 1.0, 1.0, 1.0)),
-            background_hovering: Some(Srgba::new(0.95, 0.95, 0.95, 1.0)),
-            border_radius: 3.0,
-            .. PanelBox::default()
-        },
-        Some(label_id), 3.0,
-    );
-    let pressed = button.event_pressed();
-    let button_id = ui.add_panel(button);
-
-    (button_id, pressed)
-}